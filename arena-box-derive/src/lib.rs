@@ -0,0 +1,248 @@
+//! Derive macros for the [`arena-box`](https://docs.rs/arena-box) crate.
+//!
+//! Use them through the `derive` feature of `arena-box`, which re-exports them.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::visit_mut::VisitMut;
+use syn::{Data, DeriveInput, Fields, Ident, Lifetime, Path, Type, parse_macro_input};
+
+/// Derives `IntoOwned` for a struct with at most one lifetime parameter, generating its owned
+/// mirror type.
+///
+/// For a `struct Data<'a>`, this generates a `DataOwned` struct with the same fields, where
+/// `&'a str` becomes `String`, `&'a [T]` becomes `Vec<T::Owned>`, `&'a T` becomes
+/// `Box<T::Owned>`, and any other field type becomes its `IntoOwned::Owned` type. The struct also
+/// needs a `WithLifetime` implementation, like the one generated by `make_arena_version!`.
+///
+/// # Attributes
+///
+/// On the struct:
+/// - `#[into_owned(name = DataCopy)]` names the owned type instead of `DataOwned`.
+/// - `#[into_owned(derive(Debug, PartialEq))]` adds derives to the owned type.
+///
+/// On a field:
+/// - `#[into_owned(clone)]` keeps the type of a field that is already owned, cloning it.
+/// - `#[into_owned(owned = Type, with = path)]` maps the field to `Type` by calling
+///   `path(&field)`.
+#[proc_macro_derive(IntoOwned, attributes(into_owned))]
+pub fn derive_into_owned(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_into_owned(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+enum Mapping {
+    /// Maps the field with its `IntoOwned` implementation.
+    Trait,
+    /// Maps a `&'a T` field to a `Box` of the owned `T`.
+    Boxed(Type),
+    /// Clones the field, which is already owned.
+    Clone,
+    /// Maps the field with a custom function.
+    With { owned: Type, with: Path },
+}
+
+fn expand_into_owned(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`IntoOwned` can only be derived for structs",
+        ));
+    };
+    if input.generics.type_params().next().is_some()
+        || input.generics.const_params().next().is_some()
+        || input.generics.lifetimes().count() > 1
+    {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`IntoOwned` can only be derived for structs with at most one lifetime parameter",
+        ));
+    }
+    let lifetime = input
+        .generics
+        .lifetimes()
+        .next()
+        .map(|param| param.lifetime.clone());
+
+    let ident = &input.ident;
+    let mut owned_ident = format_ident!("{}Owned", ident);
+    let mut derives = Vec::new();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("into_owned"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                owned_ident = meta.value()?.parse::<Ident>()?;
+            } else if meta.path.is_ident("derive") {
+                meta.parse_nested_meta(|derive| {
+                    derives.push(derive.path);
+                    Ok(())
+                })?;
+            } else {
+                return Err(meta.error("expected `name` or `derive`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let mut owned_fields = Vec::new();
+    let mut conversions = Vec::new();
+    let mut try_conversions = Vec::new();
+    for (index, field) in data.fields.iter().enumerate() {
+        let ty = with_static_lifetime(&field.ty, lifetime.as_ref());
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(index);
+                quote!(#index)
+            }
+        };
+        let (owned_ty, conversion, try_conversion) = match field_mapping(field, &ty)? {
+            Mapping::Trait => (
+                quote!(<#ty as ::arena_box::IntoOwned>::Owned),
+                quote!(<#ty as ::arena_box::IntoOwned>::into_owned(&value.#member)),
+                quote!(<#ty as ::arena_box::IntoOwned>::try_into_owned(&value.#member)?),
+            ),
+            Mapping::Boxed(elem) => (
+                quote!(::arena_box::__private::Box<<#elem as ::arena_box::IntoOwned>::Owned>),
+                quote!(::arena_box::__private::Box::new(
+                    <#elem as ::arena_box::IntoOwned>::into_owned(value.#member)
+                )),
+                quote!(::arena_box::__private::Box::new(
+                    <#elem as ::arena_box::IntoOwned>::try_into_owned(value.#member)?
+                )),
+            ),
+            Mapping::Clone => (
+                quote!(#ty),
+                quote!(::core::clone::Clone::clone(&value.#member)),
+                quote!(::core::clone::Clone::clone(&value.#member)),
+            ),
+            Mapping::With { owned, with } => (
+                quote!(#owned),
+                quote!(#with(&value.#member)),
+                quote!(#with(&value.#member)),
+            ),
+        };
+        let vis = &field.vis;
+        owned_fields.push(match &field.ident {
+            Some(ident) => quote!(#vis #ident: #owned_ty),
+            None => quote!(#vis #owned_ty),
+        });
+        conversions.push(match &field.ident {
+            Some(ident) => quote!(#ident: #conversion),
+            None => conversion,
+        });
+        try_conversions.push(match &field.ident {
+            Some(ident) => quote!(#ident: #try_conversion),
+            None => try_conversion,
+        });
+    }
+
+    let (owned_struct, construct, try_construct) = match &data.fields {
+        Fields::Named(_) => (
+            quote!({ #(#owned_fields,)* }),
+            quote!(#owned_ident { #(#conversions,)* }),
+            quote!(#owned_ident { #(#try_conversions,)* }),
+        ),
+        Fields::Unnamed(_) => (
+            quote!(( #(#owned_fields,)* );),
+            quote!(#owned_ident( #(#conversions,)* )),
+            quote!(#owned_ident( #(#try_conversions,)* )),
+        ),
+        Fields::Unit => (quote!(;), quote!(#owned_ident), quote!(#owned_ident)),
+    };
+    let self_ty = match lifetime {
+        Some(_) => quote!(#ident<'static>),
+        None => quote!(#ident),
+    };
+    let vis = &input.vis;
+    let doc = format!("The owned counterpart of [`{ident}`], generated by `#[derive(IntoOwned)]`.");
+
+    let derive = (!derives.is_empty()).then(|| quote!(#[derive(#(#derives),*)]));
+
+    Ok(quote! {
+        #[doc = #doc]
+        #derive
+        #vis struct #owned_ident #owned_struct
+
+        impl ::arena_box::IntoOwned for #self_ty {
+            type Owned = #owned_ident;
+
+            fn into_owned(value: &<Self as ::arena_box::WithLifetime>::With<'_>) -> #owned_ident {
+                #construct
+            }
+
+            fn try_into_owned(
+                value: &<Self as ::arena_box::WithLifetime>::With<'_>,
+            ) -> ::core::result::Result<#owned_ident, ::arena_box::__private::TryReserveError> {
+                ::core::result::Result::Ok(#try_construct)
+            }
+        }
+    })
+}
+
+fn field_mapping(field: &syn::Field, ty: &Type) -> syn::Result<Mapping> {
+    let mut mapping = None;
+    let mut owned = None;
+    let mut with = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("into_owned"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("clone") {
+                mapping = Some(Mapping::Clone);
+            } else if meta.path.is_ident("owned") {
+                owned = Some(meta.value()?.parse::<Type>()?);
+            } else if meta.path.is_ident("with") {
+                with = Some(meta.value()?.parse::<Path>()?);
+            } else {
+                return Err(meta.error("expected `clone`, `owned` or `with`"));
+            }
+            Ok(())
+        })?;
+    }
+    match (mapping, owned, with) {
+        (Some(mapping), None, None) => Ok(mapping),
+        (None, Some(owned), Some(with)) => Ok(Mapping::With { owned, with }),
+        (None, None, None) => Ok(match ty {
+            Type::Reference(reference)
+                if !matches!(&*reference.elem, Type::Slice(_))
+                    && !matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("str")) =>
+            {
+                Mapping::Boxed((*reference.elem).clone())
+            }
+            _ => Mapping::Trait,
+        }),
+        _ => Err(syn::Error::new_spanned(
+            field,
+            "expected either `clone`, or both `owned` and `with`",
+        )),
+    }
+}
+
+/// Returns `ty` with the lifetime of the struct replaced by `'static`, naming the
+/// `WithLifetime` implementation of the field type.
+fn with_static_lifetime(ty: &Type, lifetime: Option<&Lifetime>) -> Type {
+    struct ReplaceLifetime<'l>(&'l Lifetime);
+
+    impl VisitMut for ReplaceLifetime<'_> {
+        fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+            if lifetime == self.0 {
+                *lifetime = Lifetime::new("'static", lifetime.span());
+            }
+        }
+    }
+
+    let mut ty = ty.clone();
+    if let Some(lifetime) = lifetime {
+        ReplaceLifetime(lifetime).visit_type_mut(&mut ty);
+    }
+    ty
+}
@@ -0,0 +1,238 @@
+use bumpalo::{AllocErr, Bump};
+
+use crate::WithLifetime;
+
+/// A trait for deep copying arena allocated data into another arena.
+///
+/// It is implemented by this crate for the types it implements [`WithLifetime`] for, and can be
+/// implemented for your own types to use [`ArenaBox::fork`](crate::ArenaBox::fork).
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+/// use bumpalo::Bump;
+///
+/// pub struct Data<'a> {
+///     msg: &'a str,
+/// }
+///
+/// make_arena_version!(Data, pub ArenaData);
+///
+/// impl CloneInArena for Data<'static> {
+///     fn clone_in<'a>(value: &Data<'_>, arena: &'a Bump) -> Data<'a> {
+///         Data {
+///             msg: <&str>::clone_in(&value.msg, arena),
+///         }
+///     }
+/// }
+///
+/// let boxed = ArenaData::new(|arena| Data {
+///     msg: arena.alloc_str("Something"),
+/// });
+///
+/// assert_eq!(boxed.fork().get().msg, "Something");
+/// ```
+pub trait CloneInArena: WithLifetime {
+    /// Deep copies `value` into `arena`.
+    fn clone_in<'a>(value: &Self::With<'_>, arena: &'a Bump) -> Self::With<'a>;
+
+    /// Deep copies `value` into `arena`, returning an error instead of panicking if the arena
+    /// reaches its allocation limit.
+    ///
+    /// The default implementation calls [`CloneInArena::clone_in`], so it is only fallible for
+    /// types overriding it with the `try_alloc_*` methods of the arena.
+    fn try_clone_in<'a>(
+        value: &Self::With<'_>,
+        arena: &'a Bump,
+    ) -> Result<Self::With<'a>, AllocErr> {
+        Ok(Self::clone_in(value, arena))
+    }
+}
+
+impl CloneInArena for &'static str {
+    fn clone_in<'a>(value: &&str, arena: &'a Bump) -> &'a str {
+        arena.alloc_str(value)
+    }
+
+    fn try_clone_in<'a>(value: &&str, arena: &'a Bump) -> Result<&'a str, AllocErr> {
+        Ok(arena.try_alloc_str(value)?)
+    }
+}
+
+impl<E: CloneInArena> CloneInArena for &'static [E] {
+    fn clone_in<'a>(value: &&[E::With<'_>], arena: &'a Bump) -> &'a [E::With<'a>] {
+        arena.alloc_slice_fill_iter(value.iter().map(|e| E::clone_in(e, arena)))
+    }
+
+    fn try_clone_in<'a>(
+        value: &&[E::With<'_>],
+        arena: &'a Bump,
+    ) -> Result<&'a [E::With<'a>], AllocErr> {
+        Ok(try_clone_elements::<E>(value, arena)?.into_bump_slice())
+    }
+}
+
+impl<T: CloneInArena> CloneInArena for &'static T {
+    fn clone_in<'a>(value: &&T::With<'_>, arena: &'a Bump) -> &'a T::With<'a> {
+        arena.alloc(T::clone_in(value, arena))
+    }
+
+    fn try_clone_in<'a>(
+        value: &&T::With<'_>,
+        arena: &'a Bump,
+    ) -> Result<&'a T::With<'a>, AllocErr> {
+        Ok(arena.try_alloc(T::try_clone_in(value, arena)?)?)
+    }
+}
+
+impl<A: CloneInArena, B: CloneInArena> CloneInArena for (A, B) {
+    fn clone_in<'a>(
+        (a, b): &(A::With<'_>, B::With<'_>),
+        arena: &'a Bump,
+    ) -> (A::With<'a>, B::With<'a>) {
+        (A::clone_in(a, arena), B::clone_in(b, arena))
+    }
+
+    fn try_clone_in<'a>(
+        (a, b): &(A::With<'_>, B::With<'_>),
+        arena: &'a Bump,
+    ) -> Result<(A::With<'a>, B::With<'a>), AllocErr> {
+        Ok((A::try_clone_in(a, arena)?, B::try_clone_in(b, arena)?))
+    }
+}
+
+impl<T: CloneInArena> CloneInArena for bumpalo::boxed::Box<'static, T> {
+    fn clone_in<'a>(
+        value: &bumpalo::boxed::Box<'_, T::With<'_>>,
+        arena: &'a Bump,
+    ) -> bumpalo::boxed::Box<'a, T::With<'a>> {
+        bumpalo::boxed::Box::new_in(T::clone_in(value, arena), arena)
+    }
+
+    fn try_clone_in<'a>(
+        value: &bumpalo::boxed::Box<'_, T::With<'_>>,
+        arena: &'a Bump,
+    ) -> Result<bumpalo::boxed::Box<'a, T::With<'a>>, AllocErr> {
+        let value = arena.try_alloc(T::try_clone_in(value, arena)?)?;
+        // SAFETY: The value was just allocated in the arena, and nothing else references it.
+        Ok(unsafe { bumpalo::boxed::Box::from_raw(value) })
+    }
+}
+
+impl<E: CloneInArena> CloneInArena for bumpalo::collections::Vec<'static, E> {
+    fn clone_in<'a>(
+        value: &bumpalo::collections::Vec<'_, E::With<'_>>,
+        arena: &'a Bump,
+    ) -> bumpalo::collections::Vec<'a, E::With<'a>> {
+        bumpalo::collections::Vec::from_iter_in(value.iter().map(|e| E::clone_in(e, arena)), arena)
+    }
+
+    fn try_clone_in<'a>(
+        value: &bumpalo::collections::Vec<'_, E::With<'_>>,
+        arena: &'a Bump,
+    ) -> Result<bumpalo::collections::Vec<'a, E::With<'a>>, AllocErr> {
+        try_clone_elements::<E>(value, arena)
+    }
+}
+
+/// Deep copies the elements into a vector allocated in `arena` with the exact capacity.
+fn try_clone_elements<'a, E: CloneInArena>(
+    elements: &[E::With<'_>],
+    arena: &'a Bump,
+) -> Result<bumpalo::collections::Vec<'a, E::With<'a>>, AllocErr> {
+    let mut cloned = bumpalo::collections::Vec::new_in(arena);
+    cloned
+        .try_reserve_exact(elements.len())
+        .map_err(|_| AllocErr)?;
+    for element in elements {
+        cloned.push(E::try_clone_in(element, arena)?);
+    }
+    Ok(cloned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CloneInArena;
+    use crate::ArenaBox;
+
+    #[test]
+    fn test_fork() {
+        let original = ArenaBox::<(&[&str], bumpalo::collections::Vec<u32>)>::new(|arena| {
+            (
+                arena.alloc_slice_copy(&["one", "two"]),
+                bumpalo::collections::Vec::from_iter_in([1, 2], arena),
+            )
+        });
+        let mut fork = original.fork();
+        assert_eq!(fork.get().0, original.get().0);
+        assert!(!core::ptr::eq::<bumpalo::Bump>(
+            &*fork.arena,
+            &*original.arena
+        ));
+        assert_ne!(fork.get().0.as_ptr(), original.get().0.as_ptr());
+
+        {
+            let mut handle = fork.mutate();
+            handle.0 = handle.arena().alloc_slice_copy(&["three"]);
+            handle.1.push(3);
+        }
+
+        assert_eq!(original.get().0, ["one", "two"]);
+        assert_eq!(*original.get().1, [1, 2]);
+        assert_eq!(fork.get().0, ["three"]);
+        assert_eq!(*fork.get().1, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_absorb() {
+        let target = ArenaBox::<&str>::new(|arena| arena.alloc_str("target"));
+        let source = ArenaBox::<&[&str]>::new(|arena| {
+            arena.alloc_slice_fill_iter(["one", "two"].map(|s| &*arena.alloc_str(s)))
+        });
+        let source_addr = source.get().as_ptr() as usize;
+
+        let combined =
+            target.absorb::<_, (&str, &[&str]), _>(source, |_, target, source| (target, source));
+        assert_eq!(*combined.get(), ("target", &["one", "two"][..]));
+        assert_ne!(combined.get().1.as_ptr() as usize, source_addr);
+        assert_eq!(combined.arena_stats().retained_arenas, 0);
+    }
+
+    #[test]
+    fn test_shrink_arena_to() {
+        let first = ArenaBox::<&str>::new(|arena| arena.alloc_str("retained"));
+        let second = ArenaBox::<&str>::new(|arena| arena.alloc_str("other"));
+        let mut boxed = ArenaBox::<(&str, &[&str])>::new_from2(first, second, |arena, first, _| {
+            (*first, &*arena.alloc_slice_copy(&["x"; 1000][..]))
+        });
+        boxed.mutate().1 = &["shorter"];
+        let needed = boxed.fork().retained_estimate().1;
+
+        assert!(boxed.shrink_arena_to(needed - 1).is_err());
+        assert_eq!(boxed.get().1, ["shorter"]);
+        assert_eq!(boxed.retained_arenas.len(), 1);
+
+        // The tightest budget the copy fits in, given the overhead of the chunks.
+        let target = (needed..)
+            .find(|&target| boxed.shrink_arena_to(target).is_ok())
+            .unwrap();
+        assert_eq!(*boxed.get(), ("retained", &["shorter"][..]));
+        assert!(boxed.retained_arenas.is_empty());
+        assert!(boxed.allocated_bytes() <= target);
+        assert!(boxed.shrink_arena_to(target - 1).is_err());
+    }
+
+    #[test]
+    fn test_try_clone_in() {
+        let arena = bumpalo::Bump::new();
+        arena.set_allocation_limit(Some(0));
+        let words = ["one", "two"];
+        assert!(<&[&str]>::try_clone_in(&&words[..], &arena).is_err());
+
+        arena.set_allocation_limit(None);
+        let cloned = <(&[&str], &&str)>::try_clone_in(&(&words[..], &"three"), &arena).unwrap();
+        assert_eq!(cloned, (&words[..], &"three"));
+        assert!(!core::ptr::eq(cloned.0, &words[..]));
+    }
+}
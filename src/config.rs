@@ -0,0 +1,82 @@
+use bumpalo::Bump;
+
+/// The settings of the arena of an [`ArenaBox`](crate::ArenaBox), for
+/// [`ArenaBox::new_configured`](crate::ArenaBox::new_configured) and
+/// [`ArenaBox::try_new_configured`](crate::ArenaBox::try_new_configured).
+///
+/// The default configuration creates the same arena as [`ArenaBox::new`](crate::ArenaBox::new).
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let config = BumpConfig::new().chunk_capacity(4096).min_align(16);
+/// let boxed = ArenaBox::<&str>::new_configured(config, |arena| arena.alloc_str("Something"));
+///
+/// assert!(boxed.allocated_bytes() >= 4096);
+/// assert_eq!(boxed.get() as *const &str as usize % 16, 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BumpConfig {
+    pub(crate) chunk_capacity: Option<usize>,
+    pub(crate) allocation_limit: Option<usize>,
+    pub(crate) min_align: usize,
+}
+
+impl BumpConfig {
+    /// Creates the default configuration.
+    pub const fn new() -> Self {
+        BumpConfig {
+            chunk_capacity: None,
+            allocation_limit: None,
+            min_align: 1,
+        }
+    }
+
+    /// Makes the arena start with a chunk of at least `min_chunk` bytes.
+    ///
+    /// As the size of the next chunks is derived from the previous ones, this also sets the
+    /// growth increments. See [`ArenaBox::with_chunk_capacity`](crate::ArenaBox::with_chunk_capacity).
+    pub const fn chunk_capacity(mut self, min_chunk: usize) -> Self {
+        self.chunk_capacity = Some(min_chunk);
+        self
+    }
+
+    /// Limits the total capacity of the chunks of the arena to `limit` bytes.
+    ///
+    /// Unlike [`ArenaBox::with_allocation_limit`](crate::ArenaBox::with_allocation_limit), the
+    /// limit stays in place for the lifetime of the arena: once it is reached, the `alloc_*`
+    /// methods of the arena panic and the `try_alloc_*` methods fail, including while mutating.
+    /// Build with [`ArenaBox::try_new_configured`](crate::ArenaBox::try_new_configured) to get
+    /// an error instead of a panic when the limit is reached while building.
+    pub const fn allocation_limit(mut self, limit: usize) -> Self {
+        self.allocation_limit = Some(limit);
+        self
+    }
+
+    /// Aligns the data to at least `align` bytes.
+    ///
+    /// See [`ArenaBox::with_min_align`](crate::ArenaBox::with_min_align). The alignment is checked
+    /// to be a power of two when the `ArenaBox` is created.
+    pub const fn min_align(mut self, align: usize) -> Self {
+        self.min_align = align;
+        self
+    }
+
+    /// Creates an arena with the chunk capacity and the allocation limit of the configuration.
+    pub(crate) fn arena(&self) -> Bump {
+        let arena = match self.chunk_capacity {
+            Some(min_chunk) => Bump::with_capacity(min_chunk),
+            None => Bump::new(),
+        };
+        arena.set_allocation_limit(self.allocation_limit);
+        arena
+    }
+}
+
+impl Default for BumpConfig {
+    fn default() -> Self {
+        BumpConfig::new()
+    }
+}
@@ -0,0 +1,55 @@
+use bumpalo::AllocErr;
+use core::fmt;
+
+/// The error returned by the fallible constructors of [`ArenaBox`](crate::ArenaBox).
+///
+/// It tells apart running out of memory in the arena from an error returned by the build
+/// closure. Since it implements `From<AllocErr>`, the closure can use `?` on the `try_alloc_*`
+/// methods of the arena, and wrap its own errors with [`BuildError::User`].
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let result = ArenaBox::<&str>::with_allocation_limit(16, |arena| {
+///     let s = arena.try_alloc_str(&"x".repeat(1024))?;
+///     if s.is_empty() {
+///         return Err(BuildError::User("empty"));
+///     }
+///     Ok(s)
+/// });
+///
+/// assert!(matches!(result, Err(BuildError::Alloc(_))));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError<E> {
+    /// Allocating in the arena failed.
+    Alloc(AllocErr),
+    /// The build closure returned an error.
+    User(E),
+}
+
+impl<E> From<AllocErr> for BuildError<E> {
+    fn from(err: AllocErr) -> Self {
+        BuildError::Alloc(err)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for BuildError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Alloc(_) => f.write_str("out of memory in the arena"),
+            BuildError::User(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for BuildError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            BuildError::Alloc(_) => None,
+            BuildError::User(err) => Some(err),
+        }
+    }
+}
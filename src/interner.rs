@@ -0,0 +1,251 @@
+use alloc::boxed::Box;
+use bumpalo::Bump;
+use core::borrow::Borrow;
+use core::cell::RefCell;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ops::Deref;
+use core::ptr::NonNull;
+use std::collections::HashSet;
+use std::hash::RandomState;
+
+use crate::PinnedArena;
+
+/// An interner deduplicating values allocated in a single arena.
+///
+/// Interning a value returns an [`InternedRef`] to its canonical copy in the arena, allocating
+/// it only the first time the value is seen. Interned references are compared and hashed by
+/// address, which makes them cheap to use as identifiers, like the symbols of a compiler. The
+/// hash map can use any hasher, passed to [`ArenaInterner::with_hasher`].
+///
+/// Like with [`Bump::alloc`], the destructors of the interned values are not run.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let interner = ArenaInterner::<str>::new();
+///
+/// let a = interner.intern_str("ident");
+/// let b = interner.intern_str(&String::from("ident"));
+/// let c = interner.intern_str("other");
+///
+/// assert_eq!(a, b);
+/// assert_ne!(a, c);
+/// assert_eq!(&*a, "ident");
+/// assert_eq!(interner.len(), 2);
+/// ```
+pub struct ArenaInterner<T: ?Sized, S = RandomState> {
+    // The keys point into the arena, so they are declared first to be dropped before it.
+    set: RefCell<HashSet<Key<T>, S>>,
+    arena: PinnedArena,
+}
+
+impl<T: ?Sized> ArenaInterner<T> {
+    /// Creates a new empty `ArenaInterner`, using the default hasher of the standard library.
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: ?Sized> Default for ArenaInterner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized, S> ArenaInterner<T, S> {
+    /// Creates a new empty `ArenaInterner`, using `hasher` to hash the values.
+    pub fn with_hasher(hasher: S) -> Self {
+        ArenaInterner {
+            set: RefCell::new(HashSet::with_hasher(hasher)),
+            arena: Box::pin(Bump::new()),
+        }
+    }
+
+    /// Returns the number of distinct values interned.
+    pub fn len(&self) -> usize {
+        self.set.borrow().len()
+    }
+
+    /// Returns `true` if no value has been interned.
+    pub fn is_empty(&self) -> bool {
+        self.set.borrow().is_empty()
+    }
+
+    /// Returns the total capacity of the chunks allocated by the arena, in bytes.
+    pub fn allocated_bytes(&self) -> usize {
+        self.arena.allocated_bytes()
+    }
+
+    fn intern_with<V>(
+        &self,
+        value: V,
+        probe: impl FnOnce(&V) -> &T,
+        alloc: impl FnOnce(&Bump, V) -> &mut T,
+    ) -> InternedRef<'_, T>
+    where
+        T: Hash + Eq,
+        S: BuildHasher,
+    {
+        let mut set = self.set.borrow_mut();
+        let interned = match set.get(probe(&value)) {
+            Some(key) => key.0,
+            None => {
+                let interned = NonNull::from(alloc(self.arena.as_ref().get_ref(), value));
+                set.insert(Key(interned));
+                interned
+            }
+        };
+        // SAFETY: The value is in the arena, which is pinned and outlives the borrow of `self`.
+        InternedRef(unsafe { interned.as_ref() })
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> ArenaInterner<T, S> {
+    /// Returns the canonical copy of `value`, moving it into the arena if it is not interned yet.
+    pub fn intern(&self, value: T) -> InternedRef<'_, T> {
+        self.intern_with(value, |value| value, |arena, value| arena.alloc(value))
+    }
+}
+
+impl<S: BuildHasher> ArenaInterner<str, S> {
+    /// Returns the canonical copy of `s`, copying it into the arena if it is not interned yet.
+    pub fn intern_str(&self, s: &str) -> InternedRef<'_, str> {
+        self.intern_with(s, |s| *s, |arena, s| arena.alloc_str(s))
+    }
+}
+
+// A pointer to an interned value, hashed and compared by the value.
+struct Key<T: ?Sized>(NonNull<T>);
+
+impl<T: ?Sized> Borrow<T> for Key<T> {
+    fn borrow(&self) -> &T {
+        // SAFETY: The keys are dropped before the arena the values are in.
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl<T: ?Sized + Hash> Hash for Key<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Borrow::<T>::borrow(self).hash(state)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for Key<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Borrow::<T>::borrow(self) == Borrow::<T>::borrow(other)
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for Key<T> {}
+
+/// A reference to a value interned by an [`ArenaInterner`].
+///
+/// Since each value is interned once, two `InternedRef`s from the same interner are equal if
+/// and only if they point to the same value, so they are compared and hashed by address.
+pub struct InternedRef<'i, T: ?Sized>(&'i T);
+
+impl<'i, T: ?Sized> InternedRef<'i, T> {
+    /// Returns a reference to the interned value, with the lifetime of the interner borrow.
+    pub fn get(self) -> &'i T {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Clone for InternedRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for InternedRef<'_, T> {}
+
+impl<T: ?Sized> Deref for InternedRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0
+    }
+}
+
+impl<T: ?Sized> PartialEq for InternedRef<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.0, other.0)
+    }
+}
+
+impl<T: ?Sized> Eq for InternedRef<'_, T> {}
+
+impl<T: ?Sized> Hash for InternedRef<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self.0, state)
+    }
+}
+
+impl<T: ?Sized + core::fmt::Debug> core::fmt::Debug for InternedRef<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.0, f)
+    }
+}
+
+impl<T: ?Sized + core::fmt::Display> core::fmt::Display for InternedRef<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let interner = ArenaInterner::<(u32, bool)>::new();
+        let a = interner.intern((1, true));
+        let b = interner.intern((1, true));
+        let c = interner.intern((1, false));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(*c, (1, false));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn test_intern_str_with_hasher() {
+        #[derive(Default)]
+        struct Fnv(u64);
+        impl Hasher for Fnv {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+            fn write(&mut self, bytes: &[u8]) {
+                for byte in bytes {
+                    self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+                }
+            }
+        }
+
+        let interner =
+            ArenaInterner::<str, _>::with_hasher(core::hash::BuildHasherDefault::<Fnv>::default());
+        assert!(interner.is_empty());
+        let words: std::vec::Vec<_> = "a b a c b a"
+            .split(' ')
+            .map(|word| interner.intern_str(word))
+            .collect();
+        assert_eq!(interner.len(), 3);
+        assert_eq!(words[0], words[2]);
+        assert_eq!(words[1], words[4]);
+        assert_ne!(words[0], words[3]);
+        assert_eq!(
+            words
+                .iter()
+                .map(|w| w.get())
+                .collect::<std::string::String>(),
+            "abacba"
+        );
+
+        let symbols: HashSet<_> = words.into_iter().collect();
+        assert_eq!(symbols.len(), 3);
+    }
+}
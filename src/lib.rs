@@ -2,19 +2,290 @@
 #![doc = include_str!("../README.md")]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+use alloc::borrow::Cow;
 use alloc::boxed::Box;
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
 use bumpalo::Bump;
 use core::ops::{Deref, DerefMut};
 use core::pin::Pin;
 use core::ptr::NonNull;
 
+mod clone;
+mod config;
+mod error;
+#[cfg(feature = "std")]
+mod interner;
+mod owned;
+mod parse;
+mod saturating;
+mod shared;
+mod slice;
+mod stats;
+mod traced;
+
+pub use clone::CloneInArena;
+pub use config::BumpConfig;
+pub use error::BuildError;
+#[cfg(feature = "std")]
+pub use interner::{ArenaInterner, InternedRef};
+pub use owned::IntoOwned;
+pub use parse::ParseInArena;
+pub use saturating::SaturatingArena;
+pub use shared::SharedArenaBox;
+pub use slice::{ArenaSlice, ArenaSliceIntoIter};
+pub use stats::ArenaStats;
+pub use traced::TracedArena;
+
+#[cfg(feature = "derive")]
+pub use arena_box_derive::IntoOwned;
+
+// Lets the code generated by the derive macros name this crate in its own tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as arena_box;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::boxed::Box;
+    pub use alloc::collections::TryReserveError;
+}
+
 /// A trait for types that have a lifetime parameter.
+///
+/// It is implemented by [`make_arena_version!`] for your own types, and by this crate for
+/// primitive types, `&'static str`, references, slices, pairs, [`bumpalo::boxed::Box`] and
+/// [`bumpalo::collections::Vec`], so that those can be used as elements of arena allocated
+/// collections.
 pub trait WithLifetime {
     /// The type with a lifetime parameter.
     type With<'a>;
 }
 
+macro_rules! impl_with_lifetime_for_owned {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl WithLifetime for $ty {
+                type With<'a> = $ty;
+            }
+
+            impl IntoOwned for $ty {
+                type Owned = $ty;
+
+                fn into_owned(value: &$ty) -> $ty {
+                    *value
+                }
+
+                fn as_owned(value: &$ty) -> Option<&$ty> {
+                    Some(value)
+                }
+            }
+
+            impl CloneInArena for $ty {
+                fn clone_in(value: &$ty, _arena: &Bump) -> $ty {
+                    *value
+                }
+            }
+
+            // SAFETY: The type has no lifetime parameter, so it cannot borrow from the arena.
+            unsafe impl ArenaFree for $ty {}
+        )*
+    };
+}
+
+impl_with_lifetime_for_owned!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+);
+
+impl WithLifetime for &'static str {
+    type With<'a> = &'a str;
+}
+
+impl<E: WithLifetime> WithLifetime for &'static [E] {
+    type With<'a> = &'a [<E as WithLifetime>::With<'a>];
+}
+
+impl<T: WithLifetime> WithLifetime for &'static T {
+    type With<'a> = &'a <T as WithLifetime>::With<'a>;
+}
+
+impl<A: WithLifetime, B: WithLifetime> WithLifetime for (A, B) {
+    type With<'a> = (<A as WithLifetime>::With<'a>, <B as WithLifetime>::With<'a>);
+}
+
+impl<T: WithLifetime> WithLifetime for bumpalo::boxed::Box<'static, T> {
+    type With<'a> = bumpalo::boxed::Box<'a, <T as WithLifetime>::With<'a>>;
+}
+
+impl<E: WithLifetime> WithLifetime for bumpalo::collections::Vec<'static, E> {
+    type With<'a> = bumpalo::collections::Vec<'a, <E as WithLifetime>::With<'a>>;
+}
+
+/// A marker trait for types whose values never borrow from the arena.
+///
+/// It allows moving the data out of an `ArenaBox` with [`ArenaBox::into_std_box`], freeing the
+/// arena. It is implemented by this crate for primitive types and pairs of such types.
+///
+/// # Safety
+///
+/// Values of `Self::With<'a>` must not reference memory of the arena, directly or through
+/// types like [`bumpalo::boxed::Box`] or [`bumpalo::collections::Vec`], so that they stay valid
+/// after the arena is freed. This is the case when `Self::With<'a>` is `Self` and `Self` only
+/// owns heap allocated or `'static` data.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// pub struct Config {
+///     name: String,
+/// }
+///
+/// impl WithLifetime for Config {
+///     type With<'a> = Config;
+/// }
+///
+/// // SAFETY: `Config` has no lifetime parameter, so it cannot borrow from the arena.
+/// unsafe impl ArenaFree for Config {}
+///
+/// let boxed = ArenaBox::<Config>::new(|_| Config {
+///     name: String::from("Something"),
+/// });
+///
+/// let config: Box<Config> = boxed.into_std_box();
+/// assert_eq!(config.name, "Something");
+/// ```
+pub unsafe trait ArenaFree: WithLifetime {}
+
+// SAFETY: Both halves of the pair do not borrow from the arena.
+unsafe impl<A: ArenaFree, B: ArenaFree> ArenaFree for (A, B) {}
+
+/// A macro to borrow several distinct fields of the data being mutated at once.
+///
+/// It takes a [`MutHandle`], or any mutable reference, followed by a list of field paths, and
+/// returns a tuple with a mutable reference to each field. The paths can go through nested
+/// fields. The borrow checker verifies that the fields are disjoint, which is convenient for
+/// passes moving data between deeply nested fields.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// pub struct Names<'a> {
+///     current: &'a str,
+/// }
+///
+/// pub struct Data<'a> {
+///     names: Names<'a>,
+///     history: bumpalo::collections::Vec<'a, &'a str>,
+/// }
+///
+/// make_arena_version!(Data, pub ArenaData);
+///
+/// let mut boxed = ArenaData::new(|arena| Data {
+///     names: Names {
+///         current: arena.alloc_str("first"),
+///     },
+///     history: bumpalo::collections::Vec::new_in(arena),
+/// });
+///
+/// let mut handle = boxed.mutate();
+/// let arena = handle.arena();
+/// let (current, history) = split_fields!(handle, names.current, history);
+/// history.push(core::mem::replace(current, arena.alloc_str("second")));
+/// handle.finish();
+///
+/// assert_eq!(boxed.get().names.current, "second");
+/// assert_eq!(*boxed.get().history, ["first"]);
+/// ```
+///
+/// # Negative compilation test
+///
+/// The following code should fail to compile because the same field is borrowed twice.
+///
+/// ```compile_fail
+/// # use arena_box::*;
+/// # pub struct Data<'a> {
+/// #     msg: &'a str,
+/// # }
+/// # make_arena_version!(Data, ArenaData);
+/// let mut boxed = ArenaData::new(|arena| Data { msg: arena.alloc_str("Something") });
+/// let mut handle = boxed.mutate();
+/// let (a, b) = split_fields!(handle, msg, msg); // Should fail: the fields overlap
+/// *a = *b;
+/// ```
+#[macro_export]
+macro_rules! split_fields {
+    ($data:expr, $($($field:ident).+),+ $(,)?) => {{
+        let data = &mut *$data;
+        ($(&mut data.$($field).+,)+)
+    }};
+}
+
+/// A trait for comparing `ArenaBox`es by a projection of their data.
+///
+/// This is used by [`ArenaBox::eq_by`] to compare values while ignoring some of their fields,
+/// like timestamps, without defining a wrapper type with a different `PartialEq`.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// pub struct Entry<'a> {
+///     key: &'a str,
+///     timestamp: u64,
+/// }
+///
+/// make_arena_version!(Entry, pub ArenaEntry);
+///
+/// impl ArenaEqBy for Entry<'static> {
+///     type Key<'a> = &'a str;
+///
+///     fn key<'a>(value: &'a Entry<'a>) -> &'a str {
+///         value.key
+///     }
+/// }
+///
+/// let a = ArenaEntry::new(|arena| Entry {
+///     key: arena.alloc_str("config"),
+///     timestamp: 1,
+/// });
+/// let b = ArenaEntry::new(|arena| Entry {
+///     key: arena.alloc_str("config"),
+///     timestamp: 2,
+/// });
+///
+/// assert!(a.eq_by(&b));
+/// ```
+pub trait ArenaEqBy: WithLifetime {
+    /// The projection of the data that is compared.
+    type Key<'a>: PartialEq;
+
+    /// Returns the projection of `value` to compare.
+    fn key<'a>(value: &'a Self::With<'a>) -> Self::Key<'a>;
+}
+
 /// A macro to create a convenient alias for the smart pointer and also
 /// implement the required traits.
 ///
@@ -40,6 +311,23 @@ pub trait WithLifetime {
 ///
 /// make_arena_version!(Data, pub ArenaData);
 /// ```
+///
+/// Deriving `Default` on the struct makes the alias implement `Default` too, which is handy
+/// for a cheap placeholder error with empty strings, as string fields default to `""`:
+///
+/// ```
+/// # use arena_box::*;
+///
+/// #[derive(Default)]
+/// pub struct MyError<'a> {
+///    message: &'a str,
+/// }
+///
+/// make_arena_version!(MyError, pub ArenaMyError);
+///
+/// let result: Result<u32, ArenaMyError> = Err(ArenaMyError::default());
+/// assert_eq!(result.unwrap_err().get().message, "");
+/// ```
 #[macro_export]
 macro_rules! make_arena_version {
     ($name:ident, $vis:vis $alias:ident) => {
@@ -54,9 +342,14 @@ macro_rules! make_arena_version {
 /// A handle for mutating the data in an `ArenaBox`.
 ///
 /// This struct is created by the [`ArenaBox::mutate`] method.
+///
+/// Fields holding mutable references into the arena, like a `&'a mut [u8]` buffer, can be
+/// modified in place through the handle without reallocating them.
 pub struct MutHandle<'b, T: WithLifetime> {
     data: &'b mut <T as WithLifetime>::With<'b>,
     arena: &'b Bump,
+    // Bytes consumed in the arena when the handle was created, see `consumed_bytes`.
+    consumed_at_start: usize,
 }
 
 impl<'b, T: WithLifetime> MutHandle<'b, T> {
@@ -64,179 +357,118 @@ impl<'b, T: WithLifetime> MutHandle<'b, T> {
     pub fn arena(&self) -> &'b Bump {
         self.arena
     }
-}
-
-impl<'b, T: WithLifetime> Deref for MutHandle<'b, T> {
-    type Target = <T as WithLifetime>::With<'b>;
-
-    fn deref(&self) -> &Self::Target {
-        self.data
-    }
-}
-
-impl<'b, T: WithLifetime> DerefMut for MutHandle<'b, T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.data
-    }
-}
-
-/// A smart pointer that holds a struct with arena allocated objects and the arena in the same struct.
-///
-/// This is useful for creating self-referential structs.
-///
-/// # Example
-///
-/// ```
-/// # use arena_box::*;
-///
-/// pub struct Data<'a> {
-///    msg: &'a str,
-/// }
-///
-/// make_arena_version!(Data, pub ArenaData);
-///
-/// let boxed = ArenaData::new(|arena| Data {
-///     msg: arena.alloc_str("Something"),
-/// });
-///
-/// assert_eq!(boxed.get().msg, "Something");
-/// ```
-pub struct ArenaBox<T: WithLifetime> {
-    arena: Pin<Box<Bump>>,
-    data: NonNull<T>,
-}
 
-impl<T: WithLifetime> ArenaBox<T> {
-    /// Creates a new `ArenaBox`.
+    /// Allocates a value in the arena, in a [`bumpalo::boxed::Box`] that runs its destructor.
+    ///
+    /// This is a shorthand for `bumpalo::boxed::Box::new_in(value, handle.arena())`. See
+    /// [`ArenaBox`] for when destructors of arena allocated values run.
     ///
     /// # Example
     ///
     /// ```
     /// # use arena_box::*;
+    /// use bumpalo::boxed::Box;
     ///
     /// pub struct Data<'a> {
-    ///    msg: &'a str,
+    ///    children: bumpalo::collections::Vec<'a, Box<'a, String>>,
     /// }
     ///
     /// make_arena_version!(Data, pub ArenaData);
     ///
-    /// let boxed = ArenaData::new(|arena| Data {
-    ///     msg: arena.alloc_str("Something"),
+    /// let mut boxed = ArenaData::new(|arena| Data {
+    ///     children: bumpalo::collections::Vec::new_in(arena),
     /// });
     ///
-    /// assert_eq!(boxed.get().msg, "Something");
+    /// let mut handle = boxed.mutate();
+    /// let child = handle.alloc_box(String::from("child"));
+    /// handle.children.push(child);
+    ///
+    /// assert_eq!(*boxed.get().children[0], "child");
     /// ```
-    pub fn new<F>(build: F) -> Self
-    where
-        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
-    {
-        let arena = Box::pin(Bump::new());
-        let arena_ref: &Bump = arena.as_ref().get_ref();
-        let data_ref = arena_ref.alloc(build(arena_ref));
-        let data = unsafe {
-            // SAFETY: The arena is pinned, so the pointer to the data will be valid for the lifetime of the `ArenaBox`.
-            NonNull::new_unchecked(data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
-        };
-        ArenaBox { arena, data }
+    pub fn alloc_box<U>(&self, value: U) -> bumpalo::boxed::Box<'b, U> {
+        bumpalo::boxed::Box::new_in(value, self.arena)
     }
 
-    /// Creates a new `ArenaBox` by transforming data from another `ArenaBox`, reusing its arena.
+    /// Ends the mutation explicitly, returning the number of bytes it used up in the arena.
     ///
-    /// This allows you to build up data structures incrementally, where new types can reference
-    /// data from the original type. The source `ArenaBox` is consumed and its arena is moved
-    /// into the new `ArenaBox`.
+    /// Changes made through a `MutHandle` are applied to the data in place as they happen, so
+    /// apart from the returned count this is equivalent to dropping the handle. It makes the end
+    /// of a long sequence of mutations visible in the code, and releases the borrow of the
+    /// `ArenaBox`. The count includes the space left unused at the end of the chunks that filled
+    /// up while the handle was alive, so it tells how much the mutation grew the arena.
     ///
     /// # Example
     ///
     /// ```
     /// # use arena_box::*;
     ///
-    /// #[derive(Debug, PartialEq)]
     /// pub struct Data<'a> {
-    ///     msg: &'a str,
-    /// }
-    ///
-    /// #[derive(Debug, PartialEq)]
-    /// pub struct AugmentedData<'a> {
-    ///     original: &'a Data<'a>,
-    ///     extra: &'a str,
+    ///    msg: &'a str,
+    ///    count: usize,
     /// }
     ///
     /// make_arena_version!(Data, pub ArenaData);
-    /// make_arena_version!(AugmentedData, pub ArenaAugmentedData);
     ///
-    /// let data = ArenaData::new(|arena| Data {
-    ///     msg: arena.alloc_str("hello"),
+    /// let mut boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    ///     count: 0,
     /// });
     ///
-    /// // Transform Data into AugmentedData, reusing the arena
-    /// let augmented = ArenaAugmentedData::new_from(data, |arena, original| {
-    ///     AugmentedData {
-    ///         original, // Reference to the original Data!
-    ///         extra: arena.alloc_str("extra info"),
-    ///     }
-    /// });
+    /// let mut handle = boxed.mutate();
+    /// handle.msg = handle.arena().alloc_str("Something else");
+    /// handle.count += 1;
+    /// let used = handle.finish();
     ///
-    /// assert_eq!(augmented.get().original.msg, "hello");
-    /// assert_eq!(augmented.get().extra, "extra info");
+    /// assert!(used >= "Something else".len());
+    /// assert_eq!(boxed.get().msg, "Something else");
+    /// assert_eq!(boxed.get().count, 1);
     /// ```
-    pub fn new_from<U: WithLifetime, F>(source: ArenaBox<U>, build: F) -> Self
-    where
-        F: for<'a> FnOnce(
-            &'a Bump,
-            &'a <U as WithLifetime>::With<'a>,
-        ) -> <T as WithLifetime>::With<'a>,
-    {
-        let ArenaBox { arena, data } = source;
-        let arena_ref = arena.as_ref().get_ref();
-
-        let source_data = unsafe { &*(data.as_ptr() as *const <U as WithLifetime>::With<'_>) };
-
-        let new_data_ref = arena_ref.alloc(build(arena_ref, source_data));
-        let new_data = unsafe {
-            NonNull::new_unchecked(new_data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
-        };
-
-        ArenaBox {
-            arena,
-            data: new_data,
-        }
+    pub fn finish(self) -> usize {
+        consumed_bytes(self.arena) - self.consumed_at_start
     }
 
-    /// Get a reference to the data within the arena.
+    /// Ensures the current chunk of the arena has at least `additional` free bytes.
     ///
-    /// # Safety
-    ///
-    /// The returned reference is valid for the lifetime of the `ArenaBox`.
-    /// # Negative compilation test
+    /// This avoids growing the arena chunk by chunk when many small objects are about to be
+    /// allocated. If the current chunk is too small, a new chunk is allocated, and the free space
+    /// left at the end of the current one is not used anymore.
     ///
-    /// The following code should fail to compile because the lifetime of the
-    /// reference to the `Data` struct is tied to the `ArenaData` instance, and
-    /// cannot be extended beyond the lifetime of the `ArenaData` instance.
+    /// # Example
     ///
-    /// ```compile_fail
+    /// ```
     /// # use arena_box::*;
-    /// # pub struct Data<'a> {
-    /// #     msg: &'a str,
-    /// # }
-    /// # make_arena_version!(Data, ArenaData);
-    /// let message: &str;
-    /// {
-    ///     let boxed = ArenaData::new(|arena| Data {
-    ///         msg: arena.alloc_str("Something"),
-    ///     });
-    ///     message = boxed.get().msg; // Should fail: cannot extend lifetime
+    ///
+    /// pub struct Data<'a> {
+    ///    words: &'a [&'a str],
     /// }
-    /// assert_eq!(message, "Something");
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let mut boxed = ArenaData::new(|_| Data { words: &[] });
+    ///
+    /// let mut handle = boxed.mutate();
+    /// handle.reserve(16 * 1024);
+    /// assert!(handle.arena().chunk_capacity() >= 16 * 1024);
     /// ```
-    pub fn get<'b>(&'b self) -> &'b <T as WithLifetime>::With<'b> {
-        // SAFETY: The data is guaranteed to be valid for the lifetime of the `ArenaBox`.
-        unsafe { &*(self.data.as_ptr() as *const <T as WithLifetime>::With<'b>) }
+    pub fn reserve(&self, additional: usize) {
+        if self.arena.chunk_capacity() < additional {
+            // Freeing the last allocation hands its bytes back to the arena, so this only leaves
+            // a chunk large enough for `additional` bytes behind.
+            drop(bumpalo::collections::Vec::<u8>::with_capacity_in(
+                additional, self.arena,
+            ));
+        }
     }
 
-    /// Mutates the data in the `ArenaBox`.
+    /// Formats `args` into a string allocated in the arena.
     ///
-    /// This method returns a [`MutHandle`] that can be used to mutate the data.
+    /// The string is written directly into the arena with a [`bumpalo::collections::String`],
+    /// without going through an intermediate `String`. The arguments are usually created with
+    /// [`format_args!`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a formatting trait implementation returns an error.
     ///
     /// # Example
     ///
@@ -244,157 +476,5293 @@ impl<T: WithLifetime> ArenaBox<T> {
     /// # use arena_box::*;
     ///
     /// pub struct Data<'a> {
-    ///   msg: &'a str,
+    ///    msg: &'a str,
     /// }
     ///
     /// make_arena_version!(Data, pub ArenaData);
     ///
-    /// let mut boxed = ArenaData::new(|arena| Data {
-    ///    msg: arena.alloc_str("Something"),
-    /// });
+    /// let mut boxed = ArenaData::new(|_| Data { msg: "" });
     ///
-    /// assert_eq!(boxed.get().msg, "Something");
+    /// let mut handle = boxed.mutate();
+    /// handle.msg = handle.format_into(format_args!("{} items", 3));
     ///
-    /// {
-    ///    let mut handle = boxed.mutate();
-    ///    handle.msg = handle.arena().alloc_str("Something different");
+    /// assert_eq!(boxed.get().msg, "3 items");
+    /// ```
+    pub fn format_into(&self, args: core::fmt::Arguments<'_>) -> &'b str {
+        use core::fmt::Write;
+
+        let mut s = bumpalo::collections::String::new_in(self.arena);
+        s.write_fmt(args)
+            .expect("a formatting trait implementation returned an error");
+        s.into_bump_str()
+    }
+}
+
+impl<'b, T: WithLifetime> Deref for MutHandle<'b, T> {
+    type Target = <T as WithLifetime>::With<'b>;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+
+impl<'b, T: WithLifetime> DerefMut for MutHandle<'b, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data
+    }
+}
+
+/// A handle for reading the data in an `ArenaBox` alongside its arena.
+///
+/// This struct is created by the [`ArenaBox::snapshot`] method. It is the read-only counterpart
+/// of [`MutHandle`], and can be copied freely within the lifetime of the borrow.
+pub struct Snapshot<'b, T: WithLifetime> {
+    data: &'b <T as WithLifetime>::With<'b>,
+    arena: &'b Bump,
+}
+
+impl<'b, T: WithLifetime> Snapshot<'b, T> {
+    /// Returns a reference to the arena.
+    pub fn arena(&self) -> &'b Bump {
+        self.arena
+    }
+}
+
+impl<T: WithLifetime> Clone for Snapshot<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: WithLifetime> Copy for Snapshot<'_, T> {}
+
+impl<'b, T: WithLifetime> Deref for Snapshot<'b, T> {
+    type Target = <T as WithLifetime>::With<'b>;
+
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+
+/// A smart pointer that holds a struct with arena allocated objects and the arena in the same struct.
+///
+/// This is useful for creating self-referential structs.
+///
+/// # Destructors
+///
+/// Dropping an `ArenaBox` runs the destructor of its data, then frees the arena. Like in
+/// bumpalo, other values allocated in the arena are not dropped, so a field holding a plain
+/// `&'a Child` never runs the destructor of `Child`. To have it run, allocate the child in a
+/// [`bumpalo::boxed::Box`], with `bumpalo::boxed::Box::new_in(child, arena)` while building the
+/// data or with [`MutHandle::alloc_box`] while mutating it. The data of the `ArenaBox`es
+/// consumed by [`ArenaBox::new_from`] and similar methods is not dropped, since the new data
+/// may still reference it.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// pub struct Data<'a> {
+///    msg: &'a str,
+/// }
+///
+/// make_arena_version!(Data, pub ArenaData);
+///
+/// let boxed = ArenaData::new(|arena| Data {
+///     msg: arena.alloc_str("Something"),
+/// });
+///
+/// assert_eq!(boxed.get().msg, "Something");
+/// ```
+pub struct ArenaBox<T: WithLifetime> {
+    arena: PinnedArena,
+    // Arenas of the sources the data was built from, kept alive because the data may borrow from them.
+    retained_arenas: Retained,
+    data: NonNull<T>,
+    // Bytes in use in the arena when the data was last built.
+    built_bytes: usize,
+    // Whether the arena goes back to the thread-local pool when the `ArenaBox` is dropped.
+    #[cfg(feature = "std")]
+    pooled: bool,
+}
+
+/// A sub-value allocated in the arena of the `ArenaBox` holding it.
+///
+/// This is an alias for [`bumpalo::boxed::Box`], for building hierarchical data, like trees,
+/// whose nodes are all allocated in the single arena of the `ArenaBox` at the root, instead
+/// of each having an arena of its own. Like the root, sub-values have their destructor run
+/// when the `ArenaBox` is dropped. They are created with `SubBox::new_in(value, arena)` in the
+/// build closure, or with [`MutHandle::alloc_box`] while mutating.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+/// use bumpalo::collections::Vec;
+///
+/// pub struct Node<'a> {
+///     name: &'a str,
+///     children: Vec<'a, SubBox<'a, Node<'a>>>,
+/// }
+///
+/// make_arena_version!(Node, pub ArenaNode);
+///
+/// let tree = ArenaNode::new(|arena| {
+///     let leaf = |name| {
+///         SubBox::new_in(
+///             Node {
+///                 name: arena.alloc_str(name),
+///                 children: Vec::new_in(arena),
+///             },
+///             arena,
+///         )
+///     };
+///     Node {
+///         name: arena.alloc_str("root"),
+///         children: Vec::from_iter_in([leaf("left"), leaf("right")], arena),
+///     }
+/// });
+///
+/// let names: std::vec::Vec<&str> = tree.get().children.iter().map(|child| child.name).collect();
+/// assert_eq!(names, ["left", "right"]);
+/// ```
+pub type SubBox<'a, U> = bumpalo::boxed::Box<'a, U>;
+
+/// An `ArenaBox` holding a growable vector allocated in its arena.
+///
+/// This is a ready-made arena backed vector, without defining a wrapper struct. Elements are
+/// pushed with [`ArenaBox::push`], and the vector dereferences to a slice through
+/// [`ArenaBox::get`]. A vector that grows reallocates its buffer in the arena, and the old
+/// buffers stay in the arena until the `ArenaBox` is dropped.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let mut numbers = ArenaVecBox::<u32>::new_vec();
+/// numbers.push(1);
+/// numbers.push(2);
+///
+/// assert_eq!(numbers.len(), 2);
+/// assert_eq!(numbers.iter().sum::<u32>(), 3);
+/// assert_eq!(numbers.as_slice(), [1, 2]);
+/// ```
+pub type ArenaVecBox<E> = ArenaBox<bumpalo::collections::Vec<'static, E>>;
+
+pub(crate) type PinnedArena = Pin<Box<Bump>>;
+
+/// The resources of the sources an `ArenaBox` was built from, which the data may borrow from.
+#[derive(Default)]
+struct Retained {
+    arenas: Vec<PinnedArena>,
+    #[cfg(feature = "mmap")]
+    mmaps: Vec<memmap2::Mmap>,
+}
+
+impl Retained {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, arena: PinnedArena) {
+        self.arenas.push(arena);
+    }
+
+    fn extend(&mut self, other: Retained) {
+        self.arenas.extend(other.arenas);
+        #[cfg(feature = "mmap")]
+        self.mmaps.extend(other.mmaps);
+    }
+
+    fn len(&self) -> usize {
+        self.arenas.len()
+    }
+
+    #[cfg(test)]
+    fn is_empty(&self) -> bool {
+        self.arenas.is_empty()
+    }
+
+    #[cfg(feature = "debug_validate")]
+    fn arenas_mut(&mut self) -> impl Iterator<Item = &mut PinnedArena> {
+        self.arenas.iter_mut()
+    }
+
+    /// Frees the resources, poisoning the arenas first with the `debug_validate` feature.
+    fn release(self) {
+        #[cfg(feature = "debug_validate")]
+        {
+            let mut retained = self;
+            for arena in retained.arenas_mut() {
+                poison(arena.as_mut().get_mut());
+            }
+        }
+    }
+}
+
+/// Returns the number of bytes of the arena that can't be allocated anymore, in constant time.
+///
+/// Unlike [`used_bytes`], this counts the space left unused at the end of full chunks.
+fn consumed_bytes(arena: &Bump) -> usize {
+    arena.allocated_bytes() - arena.chunk_capacity()
+}
+
+/// Returns the number of bytes in use across all chunks of the arena.
+fn used_bytes(arena: &Bump) -> usize {
+    // SAFETY: No allocations are made while iterating and the chunks are never read.
+    unsafe { arena.iter_allocated_chunks_raw() }
+        .map(|(_, len)| len)
+        .sum()
+}
+
+impl<T: WithLifetime> ArenaBox<T> {
+    fn from_parts(arena: PinnedArena, retained_arenas: Retained, data: NonNull<T>) -> Self {
+        let built_bytes = used_bytes(&arena);
+        ArenaBox {
+            arena,
+            retained_arenas,
+            data,
+            built_bytes,
+            #[cfg(feature = "std")]
+            pooled: false,
+        }
+    }
+
+    fn into_parts(self) -> (PinnedArena, Retained, NonNull<T>) {
+        let this = core::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so each field is moved out exactly once.
+        unsafe {
+            (
+                core::ptr::read(&this.arena),
+                core::ptr::read(&this.retained_arenas),
+                this.data,
+            )
+        }
+    }
+
+    /// Drops the data and the retained arenas, and returns the arena reset.
+    fn into_reset_arena(self) -> Bump {
+        let (arena, retained_arenas, data) = self.into_parts();
+        // SAFETY: The data is valid and is never used again. The arenas are only freed
+        // afterwards, so the destructor can still access everything the data points to.
+        unsafe { core::ptr::drop_in_place(data.as_ptr() as *mut <T as WithLifetime>::With<'_>) };
+        let mut arena = *Pin::into_inner(arena);
+        #[cfg(feature = "debug_validate")]
+        poison(&mut arena);
+        retained_arenas.release();
+        arena.reset();
+        arena
+    }
+
+    /// Creates a new `ArenaBox`.
+    ///
+    /// # Panics
+    ///
+    /// If `build` panics, the panic is propagated and the arena is freed. No `ArenaBox` exists
+    /// until `build` has returned, so even when the panic is caught, a partially built value can
+    /// never be observed: everything the closure allocated is only reachable from inside it.
+    /// The same holds for the other constructors and for the methods consuming an `ArenaBox`
+    /// through a closure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
     /// }
     ///
-    /// assert_eq!(boxed.get().msg, "Something different");
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// assert_eq!(boxed.get().msg, "Something");
     /// ```
-    pub fn mutate<'b>(&'b mut self) -> MutHandle<'b, T> {
-        // SAFETY: The data is guaranteed to be valid for the lifetime of the `ArenaBox`.
-        let data = unsafe { &mut *(self.data.as_ptr() as *mut <T as WithLifetime>::With<'b>) };
-        let arena = self.arena.as_ref().get_ref();
-        MutHandle { data, arena }
+    pub fn new<F>(build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::build_in(Bump::new(), build)
     }
-}
 
-impl<T: core::fmt::Display + WithLifetime> core::fmt::Display for ArenaBox<T>
-where
-    for<'a> T::With<'a>: core::fmt::Display,
-{
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        core::fmt::Display::fmt(self.get(), f)
+    /// Creates a new `ArenaBox` from a value that does not borrow from an arena.
+    ///
+    /// The value is moved into a fresh arena. This is the simple case for data that owns
+    /// everything it holds, or only borrows `'static` data, so no build closure is needed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::from_owned(Data { msg: "Something" });
+    ///
+    /// assert_eq!(boxed.get().msg, "Something");
+    /// ```
+    pub fn from_owned(value: <T as WithLifetime>::With<'static>) -> Self {
+        let arena = Box::pin(Bump::new());
+        let data_ref = arena.as_ref().get_ref().alloc(value);
+        let data = unsafe {
+            // SAFETY: The arena is pinned, so the pointer to the data will be valid for the lifetime of the `ArenaBox`.
+            NonNull::new_unchecked(data_ref as *mut <T as WithLifetime>::With<'static> as *mut T)
+        };
+        Self::from_parts(arena, Retained::new(), data)
+    }
+
+    /// Creates a new `ArenaBox` with a fallible build closure.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `build`, which is a [`BuildError::Alloc`] if allocating in
+    /// the arena with one of the `try_alloc_*` methods failed, or a [`BuildError::User`] for
+    /// any other failure. The arena is freed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let parse = |input: &str| {
+    ///     ArenaData::try_new(|arena| {
+    ///         let msg = input.strip_prefix("msg=").ok_or(BuildError::User("missing key"))?;
+    ///         Ok(Data {
+    ///             msg: arena.try_alloc_str(msg)?,
+    ///         })
+    ///     })
+    /// };
+    ///
+    /// assert_eq!(parse("msg=Something").unwrap().get().msg, "Something");
+    /// assert!(matches!(parse("Something"), Err(BuildError::User("missing key"))));
+    /// ```
+    pub fn try_new<E, F>(build: F) -> Result<Self, BuildError<E>>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> Result<<T as WithLifetime>::With<'a>, BuildError<E>>,
+    {
+        Self::try_build_in(Bump::new(), build)
+    }
+
+    /// Creates a new `ArenaBox` whose data can borrow from a `'static` source without copying it.
+    ///
+    /// The build closure receives the arena and the source, so the data can mix references to
+    /// the source, like slices of a string compiled into the program, with data allocated in the
+    /// arena. The `'static` parts trivially outlive the `ArenaBox`, and only the derived data is
+    /// freed with the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Entry<'a> {
+    ///     key: &'a str,
+    ///     value: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Entry, pub ArenaEntry);
+    ///
+    /// static CONFIG: &str = "name=arena";
+    ///
+    /// let entry = ArenaEntry::new_borrowing(CONFIG, |arena, config| {
+    ///     let (key, value) = config.split_once('=').unwrap();
+    ///     Entry {
+    ///         key,
+    ///         value: arena.alloc_str(&value.to_uppercase()),
+    ///     }
+    /// });
+    ///
+    /// assert!(core::ptr::eq(entry.get().key, &CONFIG[..4]));
+    /// assert_eq!(entry.get().value, "ARENA");
+    /// ```
+    pub fn new_borrowing<S: ?Sized, F>(source: &'static S, build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump, &'static S) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::new(|arena| build(arena, source))
+    }
+
+    /// Creates a new `ArenaBox` in the provided arena.
+    ///
+    /// This allows supplying an arena that was set up beforehand, for instance one that was
+    /// [reset](Bump::reset) after a previous use and kept its largest chunk. Anything already
+    /// allocated in the arena stays there until the `ArenaBox` is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use bumpalo::Bump;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let arena = Bump::with_capacity(4096);
+    /// let boxed = ArenaData::new_in(arena, |arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// assert_eq!(boxed.get().msg, "Something");
+    /// assert!(boxed.allocated_bytes() >= 4096);
+    /// ```
+    pub fn new_in<F>(arena: Bump, build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::build_in(arena, build)
+    }
+
+    /// Creates a new `ArenaBox` in the provided arena with a fallible build closure.
+    ///
+    /// This combines [`ArenaBox::new_in`] and [`ArenaBox::try_new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `build`, which is a [`BuildError::Alloc`] if allocating in
+    /// the arena with one of the `try_alloc_*` methods failed, or a [`BuildError::User`] for
+    /// any other failure. The provided arena is dropped, freeing its chunks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use bumpalo::Bump;
+    ///
+    /// let result = ArenaBox::<&str>::try_new_in(Bump::with_capacity(4096), |arena| {
+    ///     let s = arena.try_alloc_str("")?;
+    ///     if s.is_empty() {
+    ///         return Err(BuildError::User("empty"));
+    ///     }
+    ///     Ok(s)
+    /// });
+    ///
+    /// assert!(matches!(result, Err(BuildError::User("empty"))));
+    /// ```
+    pub fn try_new_in<E, F>(arena: Bump, build: F) -> Result<Self, BuildError<E>>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> Result<<T as WithLifetime>::With<'a>, BuildError<E>>,
+    {
+        Self::try_build_in(arena, build)
+    }
+
+    /// Creates a new `ArenaBox` whose arena starts with a chunk of at least `min_chunk` bytes.
+    ///
+    /// When a chunk is full, bumpalo allocates a new one of roughly twice the size of the
+    /// previous one, so the first chunk sets the growth increments from then on. Choosing it
+    /// close to the expected total allocation size avoids both several small chunks and a large
+    /// unused tail.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::with_chunk_capacity(4096, |arena| Data {
+    ///     msg: arena.alloc_str(&"x".repeat(4000)),
+    /// });
+    ///
+    /// assert!(boxed.allocated_bytes() >= 4096);
+    /// ```
+    pub fn with_chunk_capacity<F>(min_chunk: usize, build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::build_in(Bump::with_capacity(min_chunk), build)
+    }
+
+    /// Creates a new `ArenaBox` whose arena may grow to at most `limit` bytes while building it.
+    ///
+    /// The closure receives a [`SaturatingArena`], whose allocations return an empty string or
+    /// slice instead of failing once the limit is reached. This is meant for best-effort data,
+    /// like telemetry, where losing some fields is better than failing. The limit is on the total
+    /// capacity of the chunks of the arena, as reported by [`ArenaBox::allocated_bytes`], and it
+    /// is lifted once the value is built, so later mutations can allocate normally.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Event<'a> {
+    ///     name: &'a str,
+    ///     payload: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Event, pub ArenaEvent);
+    ///
+    /// let huge = "x".repeat(64 * 1024);
+    /// let event = ArenaEvent::new_saturating(4096, |arena| Event {
+    ///     name: arena.alloc_str("upload"),
+    ///     payload: arena.alloc_str(&huge),
+    /// });
+    ///
+    /// assert_eq!(event.get().name, "upload");
+    /// assert_eq!(event.get().payload, "");
+    /// ```
+    pub fn new_saturating<F>(limit: usize, build: F) -> Self
+    where
+        F: for<'a> FnOnce(SaturatingArena<'a>) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::build_in(Bump::new(), |arena| {
+            arena.set_allocation_limit(Some(limit));
+            let value = build(SaturatingArena::new(arena));
+            // The value itself must still be allocated.
+            arena.set_allocation_limit(None);
+            value
+        })
+    }
+
+    /// Creates a new `ArenaBox`, reporting the allocations made while building it to
+    /// `callback`.
+    ///
+    /// The closure receives a [`TracedArena`], which calls `callback` with the size in bytes of
+    /// each allocation made through it, and its label if one was set with
+    /// [`TracedArena::labeled`]. This is a development aid for understanding how a complex build
+    /// closure, like a parser, uses the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///     key: &'a str,
+    ///     values: &'a [u32],
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let mut log = Vec::new();
+    /// let boxed = ArenaData::new_traced(
+    ///     |size, label| log.push((size, label.map(String::from))),
+    ///     |arena| Data {
+    ///         key: arena.labeled("key").alloc_str("numbers"),
+    ///         values: arena.alloc_slice_copy(&[1, 2, 3]),
+    ///     },
+    /// );
+    ///
+    /// assert_eq!(boxed.get().key, "numbers");
+    /// assert_eq!(log, [(7, Some("key".into())), (12, None)]);
+    /// ```
+    pub fn new_traced<C, F>(callback: C, build: F) -> Self
+    where
+        C: FnMut(usize, Option<&str>),
+        F: for<'a, 'c> FnOnce(TracedArena<'a, 'c>) -> <T as WithLifetime>::With<'a>,
+    {
+        let callback = core::cell::RefCell::new(callback);
+        Self::build_in(Bump::new(), |arena| {
+            build(TracedArena::new(arena, &callback))
+        })
+    }
+
+    /// Creates a new `ArenaBox` whose arena may allocate at most `limit` bytes while building.
+    ///
+    /// The limit applies to the chunks the arena allocates, as described by
+    /// [`Bump::set_allocation_limit`], so the allocations made by `build` must use the
+    /// `try_alloc_*` methods of the arena to fail instead of panicking once it is reached. The
+    /// limit is lifted once `build` has returned, so later mutations are not limited.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `build`: a [`BuildError::Alloc`] when the limit was
+    /// reached, or a [`BuildError::User`] for any other failure. The arena is freed.
+    ///
+    /// See [`BuildError`] for an example.
+    pub fn with_allocation_limit<E, F>(limit: usize, build: F) -> Result<Self, BuildError<E>>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> Result<<T as WithLifetime>::With<'a>, BuildError<E>>,
+    {
+        Self::try_build_in(Bump::new(), |arena| {
+            arena.set_allocation_limit(Some(limit));
+            let value = build(arena);
+            // The value itself must still be allocated.
+            arena.set_allocation_limit(None);
+            value
+        })
+    }
+
+    /// Creates a new `ArenaBox` reusing the memory of an arena pooled for the current thread.
+    ///
+    /// When an `ArenaBox` created this way is dropped, its arena is reset and kept for the next
+    /// call on the same thread, so the memory chunks allocated for one value are reused for the
+    /// next one instead of being freed and allocated again. This suits request handlers that
+    /// build many short-lived values. Only one arena is pooled per thread: if several of these
+    /// boxes are alive at once, the others get a fresh arena.
+    ///
+    /// An `ArenaBox` built from this one with [`ArenaBox::new_from`] or similar methods takes
+    /// over the arena, but does not give it back to the pool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Request<'a> {
+    ///    path: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Request, pub ArenaRequest);
+    ///
+    /// for path in ["/a", "/b"] {
+    ///     let request = ArenaRequest::from_thread_local(|arena| Request {
+    ///         path: arena.alloc_str(path),
+    ///     });
+    ///     assert_eq!(request.get().path, path);
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_thread_local<F>(build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        let arena = POOLED_ARENA
+            .try_with(|pool| pool.take())
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        let mut boxed = Self::build_in(arena, build);
+        boxed.pooled = true;
+        boxed
+    }
+
+    fn build_in<F>(arena: Bump, build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        match Self::try_build_in(arena, |arena| {
+            Ok::<_, core::convert::Infallible>(build(arena))
+        }) {
+            Ok(boxed) => boxed,
+        }
+    }
+
+    fn try_build_in<E, F>(arena: Bump, build: F) -> Result<Self, E>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> Result<<T as WithLifetime>::With<'a>, E>,
+    {
+        let arena = Box::pin(arena);
+        let arena_ref: &Bump = arena.as_ref().get_ref();
+        let data_ref = arena_ref.alloc(build(arena_ref)?);
+        let data = unsafe {
+            // SAFETY: The arena is pinned, so the pointer to the data will be valid for the lifetime of the `ArenaBox`.
+            NonNull::new_unchecked(data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+        Ok(Self::from_parts(arena, Retained::new(), data))
+    }
+
+    /// Creates a new `ArenaBox` by reading all of `reader` into the arena and parsing it.
+    ///
+    /// The input is read in chunks into a buffer in the arena, and the parse closure receives
+    /// a view of it, so the parsed data can reference the input bytes it was parsed from.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error other than [`std::io::ErrorKind::Interrupted`] returned by `reader`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Lines<'a> {
+    ///     lines: &'a [&'a [u8]],
+    /// }
+    ///
+    /// make_arena_version!(Lines, pub ArenaLines);
+    ///
+    /// let reader = std::io::Cursor::new("first\nsecond");
+    /// let boxed = ArenaLines::from_reader(reader, |arena, input| Lines {
+    ///     lines: bumpalo::collections::Vec::from_iter_in(input.split(|&b| b == b'\n'), arena)
+    ///         .into_bump_slice(),
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(boxed.get().lines, [b"first".as_slice(), b"second"]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_reader<R, F>(mut reader: R, parse: F) -> std::io::Result<Self>
+    where
+        R: std::io::Read,
+        F: for<'a> FnOnce(&'a Bump, &'a [u8]) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::try_build_in(Bump::new(), |arena| {
+            let mut input = bumpalo::collections::Vec::new_in(arena);
+            let mut chunk = [0; 8 * 1024];
+            loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(read) => input.extend_from_slice(&chunk[..read]),
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(parse(arena, input.into_bump_slice()))
+        })
+    }
+
+    /// Creates a new `ArenaBox` by memory-mapping the file at `path` and parsing it.
+    ///
+    /// The parse closure receives the arena and a view of the mapped bytes, so the file is never
+    /// read into memory. The data can borrow directly from the mapped bytes, without copying
+    /// them, and allocate what it derives from them in the arena. The `ArenaBox` keeps the
+    /// mapping alive alongside the arena, and so do the `ArenaBox`es built from it, like with
+    /// [`ArenaBox::new_from`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error from opening or mapping the file.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or another process, while the
+    /// `ArenaBox` or any `ArenaBox` built from it is alive. See [`memmap2::Mmap`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Fields<'a> {
+    ///     raw: &'a [&'a [u8]],
+    ///     upper: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Fields, pub ArenaFields);
+    ///
+    /// let path = std::env::temp_dir().join("arena_box_from_mmap_doc.csv");
+    /// std::fs::write(&path, "id,name").unwrap();
+    ///
+    /// // SAFETY: The file is not modified while it is mapped.
+    /// let fields = unsafe {
+    ///     ArenaFields::from_mmap(&path, |arena, bytes| Fields {
+    ///         // Borrowed from the mapped bytes.
+    ///         raw: arena.alloc_slice_fill_iter(bytes.split(|&b| b == b',').collect::<Vec<_>>()),
+    ///         // Allocated in the arena.
+    ///         upper: arena.alloc_str(&String::from_utf8_lossy(bytes).to_uppercase()),
+    ///     })
+    /// }
+    /// .unwrap();
+    ///
+    /// assert_eq!(fields.get().raw, [b"id".as_slice(), b"name"]);
+    /// assert_eq!(fields.get().upper, "ID,NAME");
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub unsafe fn from_mmap<P, F>(path: P, parse: F) -> std::io::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+        F: for<'a> FnOnce(&'a Bump, &'a [u8]) -> <T as WithLifetime>::With<'a>,
+    {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: The caller guarantees that the file is not modified while it is mapped.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        // SAFETY: The mapped bytes don't move when the `Mmap` handle does, and the handle is
+        // kept alive with the arena, so they stay valid for the lifetime of the `ArenaBox`.
+        let bytes = unsafe { &*(&*mmap as *const [u8]) };
+        let (arena, mut retained_arenas, data) =
+            Self::build_in(Bump::new(), |arena| parse(arena, bytes)).into_parts();
+        retained_arenas.mmaps.push(mmap);
+        Ok(Self::from_parts(arena, retained_arenas, data))
+    }
+
+    /// Creates a new `ArenaBox` whose data is aligned to at least `align` bytes.
+    ///
+    /// The data is aligned to the larger of `align` and its natural alignment, which is useful
+    /// for vectorized access to the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::with_min_align(64, |arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// assert_eq!(boxed.get() as *const Data as usize % 64, 0);
+    /// ```
+    pub fn with_min_align<F>(align: usize, build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::build_aligned_in(Bump::new(), align, build)
+    }
+
+    /// Creates a new `ArenaBox` whose arena is set up according to `config`.
+    ///
+    /// This gathers the settings of [`ArenaBox::with_chunk_capacity`] and
+    /// [`ArenaBox::with_min_align`], as well as an allocation limit, so they can be combined.
+    /// With the default configuration, this is the same as [`ArenaBox::new`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the alignment of `config` is not a power of two, or if the allocation limit of
+    /// `config` is reached while building, including when allocating the value itself. Use
+    /// [`ArenaBox::try_new_configured`] to handle reaching the limit.
+    ///
+    /// See [`BumpConfig`] for an example.
+    pub fn new_configured<F>(config: BumpConfig, build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        Self::build_aligned_in(config.arena(), config.min_align, build)
+    }
+
+    /// Creates a new `ArenaBox` whose arena is set up according to `config`, with a fallible
+    /// build closure.
+    ///
+    /// This is the fallible counterpart of [`ArenaBox::new_configured`], for configurations with
+    /// an allocation limit. Like with [`ArenaBox::with_allocation_limit`], the allocations made
+    /// by `build` must use the `try_alloc_*` methods of the arena to fail instead of panicking
+    /// once the limit is reached. Unlike it, the limit stays in place after construction.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `build`, or a [`BuildError::Alloc`] if the limit is reached
+    /// when allocating the value itself. The arena is freed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the alignment of `config` is not a power of two.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let config = BumpConfig::new().allocation_limit(1024);
+    ///
+    /// let small = ArenaBox::<&str>::try_new_configured::<(), _>(config, |arena| {
+    ///     Ok(arena.try_alloc_str("small")?)
+    /// });
+    /// assert_eq!(*small.unwrap().get(), "small");
+    ///
+    /// let large = ArenaBox::<&str>::try_new_configured::<(), _>(config, |arena| {
+    ///     Ok(arena.try_alloc_str(&"x".repeat(4096))?)
+    /// });
+    /// assert!(matches!(large, Err(BuildError::Alloc(_))));
+    /// ```
+    pub fn try_new_configured<E, F>(config: BumpConfig, build: F) -> Result<Self, BuildError<E>>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> Result<<T as WithLifetime>::With<'a>, BuildError<E>>,
+    {
+        Self::try_build_aligned_in(config.arena(), config.min_align, build)
+    }
+
+    fn build_aligned_in<F>(arena: Bump, align: usize, build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        match Self::try_build_aligned_in(arena, align, |arena| {
+            Ok::<_, BuildError<core::convert::Infallible>>(build(arena))
+        }) {
+            Ok(boxed) => boxed,
+            Err(BuildError::Alloc(_)) => panic!("out of memory"),
+        }
+    }
+
+    fn try_build_aligned_in<E, F>(
+        arena: Bump,
+        align: usize,
+        build: F,
+    ) -> Result<Self, BuildError<E>>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> Result<<T as WithLifetime>::With<'a>, BuildError<E>>,
+    {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        let arena = Box::pin(arena);
+        let arena_ref: &Bump = arena.as_ref().get_ref();
+        let value = build(arena_ref)?;
+        let layout = core::alloc::Layout::for_value(&value)
+            .align_to(align)
+            .expect("alignment overflows the size of the data");
+        let data = arena_ref.try_alloc_layout(layout)?.cast::<T>();
+        // SAFETY: The pointer was just allocated with a layout that fits the value, and the arena
+        // is pinned, so the pointer to the data will be valid for the lifetime of the `ArenaBox`.
+        unsafe { (data.as_ptr() as *mut <T as WithLifetime>::With<'_>).write(value) };
+        Ok(Self::from_parts(arena, Retained::new(), data))
+    }
+
+    /// Creates a new `ArenaBox` from a builder that also returns some metadata.
+    ///
+    /// The metadata is returned alongside the `ArenaBox` instead of being stored in the arena.
+    /// Since its type is chosen outside of the builder's lifetime, it cannot borrow from the
+    /// arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    words: &'a [&'a str],
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let (boxed, count) = ArenaData::new_with_meta(|arena| {
+    ///     let words = arena.alloc_slice_fill_iter(["hello", "world"]);
+    ///     let count = words.len();
+    ///     (Data { words }, count)
+    /// });
+    ///
+    /// assert_eq!(boxed.get().words, ["hello", "world"]);
+    /// assert_eq!(count, 2);
+    /// ```
+    ///
+    /// # Negative compilation test
+    ///
+    /// The following code should fail to compile because the metadata borrows from the arena.
+    ///
+    /// ```compile_fail
+    /// # use arena_box::*;
+    /// # pub struct Data<'a> {
+    /// #     msg: &'a str,
+    /// # }
+    /// # make_arena_version!(Data, ArenaData);
+    /// let (boxed, meta) = ArenaData::new_with_meta(|arena| {
+    ///     let msg = arena.alloc_str("Something");
+    ///     (Data { msg }, &*msg) // Should fail: metadata cannot borrow from the arena
+    /// });
+    /// ```
+    pub fn new_with_meta<F, M>(build: F) -> (Self, M)
+    where
+        F: for<'a> FnOnce(&'a Bump) -> (<T as WithLifetime>::With<'a>, M),
+    {
+        let arena = Box::pin(Bump::new());
+        let arena_ref: &Bump = arena.as_ref().get_ref();
+        let (value, meta) = build(arena_ref);
+        let data_ref = arena_ref.alloc(value);
+        let data = unsafe {
+            // SAFETY: The arena is pinned, so the pointer to the data will be valid for the lifetime of the `ArenaBox`.
+            NonNull::new_unchecked(data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+        (Self::from_parts(arena, Retained::new(), data), meta)
+    }
+
+    /// Creates a new `ArenaBox` by transforming data from another `ArenaBox`, reusing its arena.
+    ///
+    /// This allows you to build up data structures incrementally, where new types can reference
+    /// data from the original type. The source `ArenaBox` is consumed and its arena is moved
+    /// into the new `ArenaBox`.
+    ///
+    /// # Destructors
+    ///
+    /// The destructor of the source data is never run, since the new data may borrow from it, so
+    /// values of the source held in a [`bumpalo::boxed::Box`] are not dropped. The same holds for
+    /// the other methods building new data from a reference to the data of consumed
+    /// `ArenaBox`es, like [`ArenaBox::try_new_from`], [`ArenaBox::new_from2`] and the `map_*`
+    /// methods of slices. Methods receiving the data by value, like
+    /// [`ArenaBox::map_with_scratch`], drop what the closure discards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// pub struct AugmentedData<'a> {
+    ///     original: &'a Data<'a>,
+    ///     extra: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    /// make_arena_version!(AugmentedData, pub ArenaAugmentedData);
+    ///
+    /// let data = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("hello"),
+    /// });
+    ///
+    /// // Transform Data into AugmentedData, reusing the arena
+    /// let augmented = ArenaAugmentedData::new_from(data, |arena, original| {
+    ///     AugmentedData {
+    ///         original, // Reference to the original Data!
+    ///         extra: arena.alloc_str("extra info"),
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(augmented.get().original.msg, "hello");
+    /// assert_eq!(augmented.get().extra, "extra info");
+    /// ```
+    pub fn new_from<U: WithLifetime, F>(source: ArenaBox<U>, build: F) -> Self
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <U as WithLifetime>::With<'a>,
+        ) -> <T as WithLifetime>::With<'a>,
+    {
+        let (arena, retained_arenas, data) = source.into_parts();
+        let arena_ref = arena.as_ref().get_ref();
+
+        let source_data = unsafe { &*(data.as_ptr() as *const <U as WithLifetime>::With<'_>) };
+
+        let new_data_ref = arena_ref.alloc(build(arena_ref, source_data));
+        let new_data = unsafe {
+            NonNull::new_unchecked(new_data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+
+        Self::from_parts(arena, retained_arenas, new_data)
+    }
+
+    /// Creates a new `ArenaBox` by transforming data from another `ArenaBox` with a fallible
+    /// closure, reusing its arena.
+    ///
+    /// This is the fallible counterpart of [`ArenaBox::new_from`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `build`, which is a [`BuildError::Alloc`] if allocating in
+    /// the arena with one of the `try_alloc_*` methods failed, or a [`BuildError::User`] for
+    /// any other failure. The source is consumed either way, and its arena is freed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let input = ArenaBox::<&str>::new(|arena| arena.alloc_str("1,2,x"));
+    ///
+    /// let numbers = ArenaBox::<&[u32]>::try_new_from(input, |arena, input| {
+    ///     let numbers = input
+    ///         .split(',')
+    ///         .map(|n| n.parse().map_err(BuildError::User))
+    ///         .collect::<Result<Vec<u32>, _>>()?;
+    ///     Ok(&*arena.try_alloc_slice_copy(&numbers)?)
+    /// });
+    ///
+    /// assert!(matches!(numbers, Err(BuildError::User(_))));
+    /// ```
+    pub fn try_new_from<U: WithLifetime, E, F>(
+        source: ArenaBox<U>,
+        build: F,
+    ) -> Result<Self, BuildError<E>>
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <U as WithLifetime>::With<'a>,
+        ) -> Result<<T as WithLifetime>::With<'a>, BuildError<E>>,
+    {
+        let (arena, retained_arenas, data) = source.into_parts();
+        let arena_ref = arena.as_ref().get_ref();
+
+        let source_data = unsafe { &*(data.as_ptr() as *const <U as WithLifetime>::With<'_>) };
+
+        let new_data_ref = arena_ref.alloc(build(arena_ref, source_data)?);
+        let new_data = unsafe {
+            NonNull::new_unchecked(new_data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+
+        Ok(Self::from_parts(arena, retained_arenas, new_data))
+    }
+
+    /// Creates a new `ArenaBox` by combining data from two other `ArenaBox`es.
+    ///
+    /// Both sources are consumed and their arenas are kept alive by the new `ArenaBox`, so the
+    /// new data can reference data from either of them. New allocations go to the arena of
+    /// `first`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// pub struct Pair<'a> {
+    ///     first: &'a Data<'a>,
+    ///     second: &'a Data<'a>,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    /// make_arena_version!(Pair, pub ArenaPair);
+    ///
+    /// let hello = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("hello"),
+    /// });
+    /// let world = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("world"),
+    /// });
+    ///
+    /// let pair = ArenaPair::new_from2(hello, world, |_arena, first, second| Pair { first, second });
+    ///
+    /// assert_eq!(pair.get().first.msg, "hello");
+    /// assert_eq!(pair.get().second.msg, "world");
+    /// ```
+    pub fn new_from2<U: WithLifetime, V: WithLifetime, F>(
+        first: ArenaBox<U>,
+        second: ArenaBox<V>,
+        build: F,
+    ) -> Self
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <U as WithLifetime>::With<'a>,
+            &'a <V as WithLifetime>::With<'a>,
+        ) -> <T as WithLifetime>::With<'a>,
+    {
+        let (arena, mut retained_arenas, first_data) = first.into_parts();
+        let (second_arena, second_retained_arenas, second_data) = second.into_parts();
+        retained_arenas.push(second_arena);
+        retained_arenas.extend(second_retained_arenas);
+        let arena_ref = arena.as_ref().get_ref();
+
+        let first_data = unsafe { &*(first_data.as_ptr() as *const <U as WithLifetime>::With<'_>) };
+        let second_data =
+            unsafe { &*(second_data.as_ptr() as *const <V as WithLifetime>::With<'_>) };
+
+        let new_data_ref = arena_ref.alloc(build(arena_ref, first_data, second_data));
+        let new_data = unsafe {
+            NonNull::new_unchecked(new_data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+
+        Self::from_parts(arena, retained_arenas, new_data)
+    }
+
+    /// Creates a new `ArenaBox` by combining data from two other `ArenaBox`es, keeping only the
+    /// arena of `kept` alive.
+    ///
+    /// The new data can reference the data of `kept`, whose arena is moved into the new
+    /// `ArenaBox`, together with any arenas it retains. The data of `read` can only be read
+    /// while building: whatever is needed from it must be copied into the arena passed to
+    /// `build`. `read` is dropped, and its arena freed, once `build` returns. Compared to
+    /// [`ArenaBox::new_from2`], this keeps less memory alive when only one source needs to be
+    /// referenced afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// pub struct Pair<'a> {
+    ///     first: &'a Data<'a>,
+    ///     second: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    /// make_arena_version!(Pair, pub ArenaPair);
+    ///
+    /// let hello = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("hello"),
+    /// });
+    /// let world = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("world"),
+    /// });
+    ///
+    /// let pair = ArenaPair::new_from_consuming_both(hello, world, |arena, first, second| Pair {
+    ///     first,
+    ///     second: arena.alloc_str(second.msg),
+    /// });
+    ///
+    /// assert_eq!(pair.get().first.msg, "hello");
+    /// assert_eq!(pair.get().second, "world");
+    /// ```
+    ///
+    /// # Negative compilation test
+    ///
+    /// The following code should fail to compile because the data of `read` cannot be
+    /// referenced by the new data, as its arena is freed.
+    ///
+    /// ```compile_fail
+    /// # use arena_box::*;
+    /// # pub struct Data<'a> {
+    /// #     msg: &'a str,
+    /// # }
+    /// # make_arena_version!(Data, ArenaData);
+    /// let kept = ArenaData::new(|arena| Data { msg: arena.alloc_str("kept") });
+    /// let read = ArenaData::new(|arena| Data { msg: arena.alloc_str("read") });
+    /// let boxed = ArenaData::new_from_consuming_both(kept, read, |_, _, read| Data {
+    ///     msg: read.msg, // Should fail: must be copied into the arena
+    /// });
+    /// ```
+    pub fn new_from_consuming_both<U: WithLifetime, V: WithLifetime, F>(
+        kept: ArenaBox<U>,
+        read: ArenaBox<V>,
+        build: F,
+    ) -> Self
+    where
+        F: for<'a, 'r> FnOnce(
+            &'a Bump,
+            &'a <U as WithLifetime>::With<'a>,
+            &'r <V as WithLifetime>::With<'r>,
+        ) -> <T as WithLifetime>::With<'a>,
+    {
+        let (arena, retained_arenas, kept_data) = kept.into_parts();
+        let arena_ref = arena.as_ref().get_ref();
+
+        let kept_data = unsafe { &*(kept_data.as_ptr() as *const <U as WithLifetime>::With<'_>) };
+
+        let new_data_ref = arena_ref.alloc(build(arena_ref, kept_data, read.get()));
+        let new_data = unsafe {
+            NonNull::new_unchecked(new_data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+        drop(read);
+
+        Self::from_parts(arena, retained_arenas, new_data)
+    }
+
+    /// Creates a new `ArenaBox` by combining data from three other `ArenaBox`es.
+    ///
+    /// This is the same as [`ArenaBox::new_from2`], but for three sources. All their arenas are
+    /// kept alive by the new `ArenaBox`, and new allocations go to the arena of `first`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// pub struct Sentence<'a> {
+    ///     words: [&'a str; 3],
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    /// make_arena_version!(Sentence, pub ArenaSentence);
+    ///
+    /// let word = |msg: &str| ArenaData::new(|arena| Data { msg: arena.alloc_str(msg) });
+    ///
+    /// let sentence = ArenaSentence::new_from3(
+    ///     word("arenas"),
+    ///     word("are"),
+    ///     word("fun"),
+    ///     |_arena, a, b, c| Sentence {
+    ///         words: [a.msg, b.msg, c.msg],
+    ///     },
+    /// );
+    ///
+    /// assert_eq!(sentence.get().words.join(" "), "arenas are fun");
+    /// ```
+    pub fn new_from3<U: WithLifetime, V: WithLifetime, W: WithLifetime, F>(
+        first: ArenaBox<U>,
+        second: ArenaBox<V>,
+        third: ArenaBox<W>,
+        build: F,
+    ) -> Self
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <U as WithLifetime>::With<'a>,
+            &'a <V as WithLifetime>::With<'a>,
+            &'a <W as WithLifetime>::With<'a>,
+        ) -> <T as WithLifetime>::With<'a>,
+    {
+        let (arena, mut retained_arenas, first_data) = first.into_parts();
+        let (second_arena, second_retained_arenas, second_data) = second.into_parts();
+        retained_arenas.push(second_arena);
+        retained_arenas.extend(second_retained_arenas);
+        let (third_arena, third_retained_arenas, third_data) = third.into_parts();
+        retained_arenas.push(third_arena);
+        retained_arenas.extend(third_retained_arenas);
+        let arena_ref = arena.as_ref().get_ref();
+
+        let first_data = unsafe { &*(first_data.as_ptr() as *const <U as WithLifetime>::With<'_>) };
+        let second_data =
+            unsafe { &*(second_data.as_ptr() as *const <V as WithLifetime>::With<'_>) };
+        let third_data = unsafe { &*(third_data.as_ptr() as *const <W as WithLifetime>::With<'_>) };
+
+        let new_data_ref = arena_ref.alloc(build(arena_ref, first_data, second_data, third_data));
+        let new_data = unsafe {
+            NonNull::new_unchecked(new_data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+
+        Self::from_parts(arena, retained_arenas, new_data)
+    }
+
+    /// Creates a new `ArenaBox` by combining data from any number of other `ArenaBox`es.
+    ///
+    /// The new data is built in a fresh arena, and the arenas of all the sources are kept alive
+    /// for as long as the new `ArenaBox`, so the new data can reference all of their data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Fragment<'a> {
+    ///     code: &'a str,
+    /// }
+    ///
+    /// pub struct Bundle<'a> {
+    ///     fragments: &'a [&'a Fragment<'a>],
+    ///     code: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Fragment, pub ArenaFragment);
+    /// make_arena_version!(Bundle, pub ArenaBundle);
+    ///
+    /// let fragments = ["a();", "b();"]
+    ///     .into_iter()
+    ///     .map(|code| ArenaFragment::new(|arena| Fragment { code: arena.alloc_str(code) }))
+    ///     .collect();
+    ///
+    /// let bundle = ArenaBundle::new_from_slice(fragments, |arena, fragments| Bundle {
+    ///     fragments,
+    ///     code: arena.alloc_str(&fragments.iter().map(|f| f.code).collect::<String>()),
+    /// });
+    ///
+    /// assert_eq!(bundle.get().fragments[1].code, "b();");
+    /// assert_eq!(bundle.get().code, "a();b();");
+    /// ```
+    pub fn new_from_slice<U: WithLifetime, F>(sources: Vec<ArenaBox<U>>, build: F) -> Self
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a [&'a <U as WithLifetime>::With<'a>],
+        ) -> <T as WithLifetime>::With<'a>,
+    {
+        let arena = Box::pin(Bump::new());
+        let arena_ref = arena.as_ref().get_ref();
+
+        let mut retained_arenas = Retained::new();
+        let mut source_data = Vec::with_capacity(sources.len());
+        for source in sources {
+            let (source_arena, source_retained_arenas, data) = source.into_parts();
+            retained_arenas.push(source_arena);
+            retained_arenas.extend(source_retained_arenas);
+            source_data.push(data);
+        }
+        let source_data = arena_ref.alloc_slice_fill_iter(
+            source_data
+                .into_iter()
+                .map(|data| unsafe { &*(data.as_ptr() as *const <U as WithLifetime>::With<'_>) }),
+        );
+
+        let new_data_ref = arena_ref.alloc(build(arena_ref, source_data));
+        let new_data = unsafe {
+            NonNull::new_unchecked(new_data_ref as *mut <T as WithLifetime>::With<'_> as *mut T)
+        };
+
+        Self::from_parts(arena, retained_arenas, new_data)
+    }
+
+    /// Turns the error of a fallible operation on the data into an `ArenaBox` that reuses this
+    /// arena.
+    ///
+    /// On `Ok`, returns the `ArenaBox` back together with the value. On `Err`, the `ArenaBox` is
+    /// consumed like in [`ArenaBox::new_from`], and the closure builds the error in its arena,
+    /// so the error can reference the data that caused it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Config<'a> {
+    ///     port: &'a str,
+    /// }
+    ///
+    /// pub struct ConfigError<'a> {
+    ///     config: &'a Config<'a>,
+    ///     message: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Config, pub ArenaConfig);
+    /// make_arena_version!(ConfigError, pub ArenaConfigError);
+    ///
+    /// let config = ArenaConfig::new(|arena| Config {
+    ///     port: arena.alloc_str("eighty"),
+    /// });
+    ///
+    /// let port = config.get().port.parse::<u16>();
+    /// let result: Result<_, ArenaConfigError> =
+    ///     config.map_err_arena(port, |arena, config, err| ConfigError {
+    ///         config,
+    ///         message: arena.alloc_str(&err.to_string()),
+    ///     });
+    ///
+    /// let Err(err) = result else {
+    ///     panic!("Expected an error");
+    /// };
+    /// assert_eq!(err.get().config.port, "eighty");
+    /// assert_eq!(err.get().message, "invalid digit found in string");
+    /// ```
+    pub fn map_err_arena<R, E, U: WithLifetime, F>(
+        self,
+        result: Result<R, E>,
+        f: F,
+    ) -> Result<(Self, R), ArenaBox<U>>
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <T as WithLifetime>::With<'a>,
+            E,
+        ) -> <U as WithLifetime>::With<'a>,
+    {
+        match result {
+            Ok(value) => Ok((self, value)),
+            Err(err) => Err(ArenaBox::new_from(self, move |arena, data| {
+                f(arena, data, err)
+            })),
+        }
+    }
+
+    /// Transforms the data in the `ArenaBox`, returning a side result along with the new
+    /// `ArenaBox`.
+    ///
+    /// The closure receives the arena and the current value, like [`ArenaBox::new_from`], and
+    /// returns the new value together with a result, like a report of what the transform did.
+    /// Since the type of the result is chosen outside of the closure, it cannot borrow from the
+    /// arena. The old value is not freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let words = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["a", "", "b"]));
+    ///
+    /// let (words, removed) = words.map_result(|arena, words| {
+    ///     let kept = bumpalo::collections::Vec::from_iter_in(
+    ///         words.iter().copied().filter(|word| !word.is_empty()),
+    ///         arena,
+    ///     );
+    ///     let removed = words.len() - kept.len();
+    ///     (kept.into_bump_slice(), removed)
+    /// });
+    ///
+    /// assert_eq!(*words.get(), ["a", "b"]);
+    /// assert_eq!(removed, 1);
+    /// ```
+    pub fn map_result<R, F>(self, f: F) -> (Self, R)
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <T as WithLifetime>::With<'a>,
+        ) -> (<T as WithLifetime>::With<'a>, R),
+    {
+        let mut result = None;
+        let boxed = ArenaBox::new_from(self, |arena, old| {
+            let (new, r) = f(arena, old);
+            result = Some(r);
+            new
+        });
+        (boxed, result.expect("the closure is always called"))
+    }
+
+    /// Derives a new value from the data in the `ArenaBox`, keeping the old value alongside it.
+    ///
+    /// The resulting `ArenaBox` holds a pair of a reference to the old value and the new value,
+    /// which may borrow from the old one. Both stay in the same arena, so no separate type
+    /// combining them needs to be defined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Source<'a> {
+    ///     text: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Source, pub ArenaSource);
+    ///
+    /// pub struct Tokens<'a> {
+    ///     words: &'a [&'a str],
+    /// }
+    ///
+    /// make_arena_version!(Tokens, pub ArenaTokens);
+    ///
+    /// let source = ArenaSource::new(|arena| Source {
+    ///     text: arena.alloc_str("let x = 1"),
+    /// });
+    ///
+    /// let pass = source.augment::<Tokens<'static>, _>(|arena, source| Tokens {
+    ///     words: arena.alloc_slice_fill_iter(source.text.split(' ').collect::<Vec<_>>()),
+    /// });
+    ///
+    /// let (source, tokens) = pass.get();
+    /// assert_eq!(source.text, "let x = 1");
+    /// assert_eq!(tokens.words, ["let", "x", "=", "1"]);
+    /// ```
+    pub fn augment<N: WithLifetime, F>(self, f: F) -> ArenaBox<(&'static T, N)>
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <T as WithLifetime>::With<'a>,
+        ) -> <N as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new_from(self, |arena, old| {
+            let new = f(arena, old);
+            (old, new)
+        })
+    }
+
+    /// Derives a new value from the data in the `ArenaBox`, storing both in a pair.
+    ///
+    /// Unlike [`ArenaBox::augment`], the old value is moved into the pair instead of being
+    /// referenced from it. The new value can borrow anything the old value points to in the
+    /// arena, but not the old value itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Source<'a> {
+    ///     text: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Source, pub ArenaSource);
+    ///
+    /// let source = ArenaSource::new(|arena| Source {
+    ///     text: arena.alloc_str("let x = 1"),
+    /// });
+    ///
+    /// let pass = source.map_both::<&'static str, _>(|_arena, source| {
+    ///     source.text.split(' ').nth(1).unwrap()
+    /// });
+    ///
+    /// let (source, name) = pass.get();
+    /// assert_eq!(source.text, "let x = 1");
+    /// assert_eq!(*name, "x");
+    /// ```
+    pub fn map_both<N: WithLifetime, F>(self, f: F) -> ArenaBox<(T, N)>
+    where
+        F: for<'a, 'o> FnOnce(
+            &'a Bump,
+            &'o <T as WithLifetime>::With<'a>,
+        ) -> <N as WithLifetime>::With<'a>,
+    {
+        let (arena, retained_arenas, data) = self.into_parts();
+        let arena_ref = arena.as_ref().get_ref();
+
+        // SAFETY: The value is moved out of its slot, which is never accessed again.
+        let old = unsafe { core::ptr::read(data.as_ptr() as *const <T as WithLifetime>::With<'_>) };
+        let new = f(arena_ref, &old);
+        let pair_ref = arena_ref.alloc((old, new));
+        let pair = unsafe {
+            NonNull::new_unchecked(
+                pair_ref as *mut <(T, N) as WithLifetime>::With<'_> as *mut (T, N),
+            )
+        };
+
+        ArenaBox::from_parts(arena, retained_arenas, pair)
+    }
+
+    /// Transforms the data into a richer structure embedding it, reusing the arena.
+    ///
+    /// Unlike [`ArenaBox::new_from`], which lends the closure a reference to the old value, the
+    /// old value is moved into the closure, which can move its fields into the new value. Only
+    /// the fields themselves are moved: the data they reference stays where it is in the arena,
+    /// so moving a `&'a str` or a `bumpalo::boxed::Box` is a pointer copy. The fields that are
+    /// not moved out are dropped when the closure returns, and the new value is allocated in
+    /// the arena, which is not reset.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Node<'a> {
+    ///     name: &'a str,
+    ///     children: &'a [&'a str],
+    /// }
+    ///
+    /// make_arena_version!(Node, pub ArenaNode);
+    ///
+    /// pub struct TypedNode<'a> {
+    ///     node: Node<'a>,
+    ///     ty: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(TypedNode, pub ArenaTypedNode);
+    ///
+    /// let node = ArenaNode::new(|arena| Node {
+    ///     name: arena.alloc_str("add"),
+    ///     children: arena.alloc_slice_copy(&["x", "y"]),
+    /// });
+    ///
+    /// let typed = node.grow_into::<TypedNode, _>(|arena, node| TypedNode {
+    ///     node,
+    ///     ty: arena.alloc_str("i32"),
+    /// });
+    ///
+    /// assert_eq!(typed.get().node.children, ["x", "y"]);
+    /// assert_eq!(typed.get().ty, "i32");
+    /// ```
+    pub fn grow_into<N: WithLifetime, F>(self, f: F) -> ArenaBox<N>
+    where
+        F: for<'a> FnOnce(&'a Bump, <T as WithLifetime>::With<'a>) -> <N as WithLifetime>::With<'a>,
+    {
+        let (arena, retained_arenas, data) = self.into_parts();
+        let arena_ref = arena.as_ref().get_ref();
+
+        // SAFETY: The value is moved out of its slot, which is never accessed again.
+        let old = unsafe { core::ptr::read(data.as_ptr() as *const <T as WithLifetime>::With<'_>) };
+        let new_ref = arena_ref.alloc(f(arena_ref, old));
+        let new = unsafe {
+            NonNull::new_unchecked(new_ref as *mut <N as WithLifetime>::With<'_> as *mut N)
+        };
+
+        ArenaBox::from_parts(arena, retained_arenas, new)
+    }
+
+    /// Narrows the `ArenaBox` to a part of its data, keeping the whole arena alive.
+    ///
+    /// The closure receives the data and returns a mutable reference to a value it owns, like a
+    /// field or the target of an arena allocated box. The new `ArenaBox` points at that value.
+    /// Unlike building a new value with [`ArenaBox::new_from`], this does not copy or move the
+    /// selected value.
+    ///
+    /// This is a safe method taking the data by mutable reference, rather than an `unsafe` one
+    /// selecting through a shared reference. The mutable reference guarantees that the selected
+    /// value is owned by the data and that nothing else aliases it, so the new `ArenaBox` can
+    /// mutate and drop it. A shared reference could point to a value that something else in the
+    /// data borrows too, or to one that the data doesn't own at all, like a `'static` value.
+    ///
+    /// # Destructors
+    ///
+    /// Only the selected value is dropped with the new `ArenaBox`. The rest of the data stays in
+    /// the arena but is no longer reachable, and its destructors are never run: narrowing a
+    /// struct to one of its fields skips the destructors of the other fields, including the
+    /// values they hold in a [`bumpalo::boxed::Box`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Header<'a> {
+    ///     name: &'a str,
+    /// }
+    ///
+    /// pub struct Message<'a> {
+    ///     header: Header<'a>,
+    ///     body: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Header, pub ArenaHeader);
+    /// make_arena_version!(Message, pub ArenaMessage);
+    ///
+    /// let message = ArenaMessage::new(|arena| Message {
+    ///     header: Header {
+    ///         name: arena.alloc_str("greeting"),
+    ///     },
+    ///     body: arena.alloc_str("hello"),
+    /// });
+    ///
+    /// let header: ArenaHeader = message.retarget(|message| &mut message.header);
+    ///
+    /// assert_eq!(header.get().name, "greeting");
+    /// ```
+    pub fn retarget<U: WithLifetime, F>(self, select: F) -> ArenaBox<U>
+    where
+        F: for<'a> FnOnce(
+            &'a mut <T as WithLifetime>::With<'a>,
+        ) -> &'a mut <U as WithLifetime>::With<'a>,
+    {
+        let (arena, retained_arenas, data) = self.into_parts();
+
+        // SAFETY: The data is valid for the lifetime of the arenas, which are moved into the new
+        // `ArenaBox`, and the old data is never accessed again except through the selected value.
+        let data = unsafe { &mut *(data.as_ptr() as *mut <T as WithLifetime>::With<'_>) };
+        let selected = NonNull::from(select(data)).cast::<U>();
+
+        ArenaBox::from_parts(arena, retained_arenas, selected)
+    }
+
+    /// Transforms the data in the `ArenaBox`, with access to a temporary scratch arena.
+    ///
+    /// The closure receives the arena of the `ArenaBox`, a scratch arena and the current value,
+    /// and returns the new value. Everything allocated in the scratch arena is freed as soon as
+    /// the closure returns, so large intermediate results don't end up in the arena of the
+    /// `ArenaBox`. The new value cannot borrow from the scratch arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("hello big world"),
+    /// });
+    ///
+    /// let boxed = boxed.map_with_scratch(|arena, scratch, data| {
+    ///     // The intermediate vector is freed once the transform is done.
+    ///     let words = bumpalo::collections::Vec::from_iter_in(data.msg.split(' '), scratch);
+    ///     Data {
+    ///         msg: arena.alloc_str(words[1]),
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(boxed.get().msg, "big");
+    /// ```
+    pub fn map_with_scratch<F>(self, f: F) -> Self
+    where
+        F: for<'a, 's> FnOnce(
+            &'a Bump,
+            &'s Bump,
+            <T as WithLifetime>::With<'a>,
+        ) -> <T as WithLifetime>::With<'a>,
+    {
+        let (arena, retained_arenas, data) = self.into_parts();
+        let arena_ref = arena.as_ref().get_ref();
+        let scratch = Bump::new();
+
+        // SAFETY: The value is moved out of the arena and handed to the closure, and the slot is
+        // overwritten with the new value before the `ArenaBox` is put back together.
+        let old = unsafe { core::ptr::read(data.as_ptr() as *const <T as WithLifetime>::With<'_>) };
+        let new = f(arena_ref, &scratch, old);
+        drop(scratch);
+        unsafe { core::ptr::write(data.as_ptr() as *mut <T as WithLifetime>::With<'_>, new) };
+
+        Self::from_parts(arena, retained_arenas, data)
+    }
+
+    /// Transforms the data in the `ArenaBox` with an asynchronous closure.
+    ///
+    /// The closure receives the arena of the `ArenaBox` and the current value, and returns a
+    /// boxed future resolving to the new value. The future can hold the arena across `.await`
+    /// points, and allocate the new value in it.
+    ///
+    /// If the returned future is dropped before completing, the `ArenaBox` is dropped with it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// # fn block_on<F: Future>(future: F) -> F::Output {
+    /// #     let mut future = core::pin::pin!(future);
+    /// #     let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+    /// #     loop {
+    /// #         if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+    /// #             return output;
+    /// #         }
+    /// #     }
+    /// # }
+    ///
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// async fn fetch_suffix() -> &'static str {
+    ///     " world"
+    /// }
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("hello"),
+    /// });
+    ///
+    /// let boxed = block_on(boxed.map_async(|arena, data| {
+    ///     Box::pin(async move {
+    ///         let suffix = fetch_suffix().await;
+    ///         Data {
+    ///             msg: arena.alloc_str(&format!("{}{}", data.msg, suffix)),
+    ///         }
+    ///     })
+    /// }));
+    ///
+    /// assert_eq!(boxed.get().msg, "hello world");
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn map_async<F>(self, f: F) -> Self
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            <T as WithLifetime>::With<'a>,
+        )
+            -> Pin<Box<dyn Future<Output = <T as WithLifetime>::With<'a>> + 'a>>,
+    {
+        let (arena, retained_arenas, data) = self.into_parts();
+        let arena_ref = arena.as_ref().get_ref();
+
+        // SAFETY: The value is moved out of the arena and handed to the closure, and the slot is
+        // overwritten with the new value before the `ArenaBox` is put back together. If the
+        // future is dropped in between, the slot is never read again.
+        let old = unsafe { core::ptr::read(data.as_ptr() as *const <T as WithLifetime>::With<'_>) };
+        let new = f(arena_ref, old).await;
+        unsafe { core::ptr::write(data.as_ptr() as *mut <T as WithLifetime>::With<'_>, new) };
+
+        Self::from_parts(arena, retained_arenas, data)
+    }
+
+    /// Get a reference to the data within the arena.
+    ///
+    /// # Safety
+    ///
+    /// The returned reference is valid for the lifetime of the `ArenaBox`.
+    /// # Negative compilation test
+    ///
+    /// The following code should fail to compile because the lifetime of the
+    /// reference to the `Data` struct is tied to the `ArenaData` instance, and
+    /// cannot be extended beyond the lifetime of the `ArenaData` instance.
+    ///
+    /// ```compile_fail
+    /// # use arena_box::*;
+    /// # pub struct Data<'a> {
+    /// #     msg: &'a str,
+    /// # }
+    /// # make_arena_version!(Data, ArenaData);
+    /// let message: &str;
+    /// {
+    ///     let boxed = ArenaData::new(|arena| Data {
+    ///         msg: arena.alloc_str("Something"),
+    ///     });
+    ///     message = boxed.get().msg; // Should fail: cannot extend lifetime
+    /// }
+    /// assert_eq!(message, "Something");
+    /// ```
+    pub fn get<'b>(&'b self) -> &'b <T as WithLifetime>::With<'b> {
+        // SAFETY: The data is guaranteed to be valid for the lifetime of the `ArenaBox`.
+        unsafe { &*(self.data.as_ptr() as *const <T as WithLifetime>::With<'b>) }
+    }
+
+    /// Get a reference to the data within the arena, with the arena lifetime shortened to
+    /// `'short`.
+    ///
+    /// This is identical to [`ArenaBox::get`]: it spells out that both the reference and the
+    /// lifetime of the data are the borrow of the `ArenaBox`. The data is really valid for as
+    /// long as the arena is, which is longer, and shortening that lifetime is always sound. The
+    /// reverse is not: neither the reference nor the data can be extended past the borrow.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// fn first_word<'short>(data: &'short Data<'short>) -> &'short str {
+    ///     data.msg.split(' ').next().unwrap()
+    /// }
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("Something else"),
+    /// });
+    ///
+    /// assert_eq!(first_word(boxed.get_for()), "Something");
+    /// ```
+    ///
+    /// # Negative compilation test
+    ///
+    /// The following code should fail to compile because the data cannot be given a lifetime
+    /// longer than the borrow of the `ArenaData` instance.
+    ///
+    /// ```compile_fail
+    /// # use arena_box::*;
+    /// # pub struct Data<'a> {
+    /// #     msg: &'a str,
+    /// # }
+    /// # make_arena_version!(Data, ArenaData);
+    /// fn extend<'short>(boxed: &'short ArenaData) -> &'short Data<'static> {
+    ///     boxed.get_for() // Should fail: cannot extend the lifetime of the data
+    /// }
+    /// ```
+    pub fn get_for<'short>(&'short self) -> &'short <T as WithLifetime>::With<'short> {
+        self.get()
+    }
+
+    /// Moves the data out of the arena into a standard `Box`, freeing the arena.
+    ///
+    /// This requires `T: ArenaFree`, which guarantees that the data does not borrow from the
+    /// arena, and so stays valid once it is freed. See [`ArenaFree`] for an example.
+    pub fn into_std_box(self) -> Box<<T as WithLifetime>::With<'static>>
+    where
+        T: ArenaFree,
+    {
+        let (arena, retained_arenas, data) = self.into_parts();
+        // SAFETY: The value is moved out of its slot, which is never accessed again, and
+        // `ArenaFree` guarantees it does not reference the arenas freed below.
+        let value =
+            unsafe { core::ptr::read(data.as_ptr() as *const <T as WithLifetime>::With<'static>) };
+        drop(retained_arenas);
+        drop(arena);
+        Box::new(value)
+    }
+
+    /// Returns `true` if the projections of the data given by [`ArenaEqBy::key`] are equal.
+    ///
+    /// See [`ArenaEqBy`] for an example.
+    pub fn eq_by(&self, other: &Self) -> bool
+    where
+        T: ArenaEqBy,
+    {
+        T::key(self.get()) == T::key(other.get())
+    }
+
+    /// Returns `true` if the data of both `ArenaBox`es are equal, always comparing them by
+    /// value.
+    ///
+    /// The `PartialEq` implementation considers an `ArenaBox` equal to itself without comparing
+    /// the data, which differs when the data is not equal to itself, like a `NaN` float. This
+    /// method never takes that shortcut, so it has the exact semantics of `PartialEq` for the
+    /// data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let nan = ArenaBox::<f64>::new(|_| f64::NAN);
+    ///
+    /// assert!(nan == nan);
+    /// assert!(!nan.structural_eq(&nan));
+    /// ```
+    pub fn structural_eq(&self, other: &Self) -> bool
+    where
+        for<'a> T::With<'a>: PartialEq,
+    {
+        self.get() == other.get()
+    }
+
+    /// Returns a raw pointer to the data within the arena, for passing it to foreign code.
+    ///
+    /// The pointer is valid for reads for as long as the `ArenaBox` is alive and not mutated.
+    /// The value is laid out exactly as `T::With` is, so if it is `#[repr(C)]`, and so are the
+    /// types it points to, C code can read its fields directly. No check is made that the type
+    /// is FFI-safe.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// #[repr(C)]
+    /// pub struct Point<'a> {
+    ///     x: i32,
+    ///     y: i32,
+    ///     label: &'a u8,
+    /// }
+    ///
+    /// make_arena_version!(Point, pub ArenaPoint);
+    ///
+    /// let boxed = ArenaPoint::new(|arena| Point {
+    ///     x: 1,
+    ///     y: 2,
+    ///     label: arena.alloc(b'p'),
+    /// });
+    ///
+    /// let ptr = boxed.as_ffi_ptr() as *const i32;
+    /// assert_eq!(unsafe { *ptr.add(1) }, 2);
+    /// ```
+    pub fn as_ffi_ptr<'b>(&'b self) -> *const <T as WithLifetime>::With<'b> {
+        self.data.as_ptr() as *const <T as WithLifetime>::With<'b>
+    }
+
+    /// Returns a [`Snapshot`] bundling a reference to the data and the arena.
+    ///
+    /// Allocations made through the arena of the snapshot live as long as the `ArenaBox`, so
+    /// they should be kept small.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// let snapshot = boxed.snapshot();
+    /// let upper = snapshot.arena().alloc_str(&snapshot.msg.to_uppercase());
+    ///
+    /// assert_eq!(upper, "SOMETHING");
+    /// ```
+    pub fn snapshot<'b>(&'b self) -> Snapshot<'b, T> {
+        Snapshot {
+            data: self.get(),
+            arena: self.arena.as_ref().get_ref(),
+        }
+    }
+
+    /// Deep copies the data out of the arena into its owned counterpart.
+    ///
+    /// See [`IntoOwned`] for an example.
+    pub fn into_owned(&self) -> <T as IntoOwned>::Owned
+    where
+        T: IntoOwned,
+    {
+        T::into_owned(self.get())
+    }
+
+    /// Deep copies the data out of the arena into its owned counterpart, returning an error
+    /// instead of aborting if allocating the owned value fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let boxed = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["a", "b"]));
+    ///
+    /// let owned: Vec<String> = boxed.try_into_owned().unwrap();
+    /// assert_eq!(owned, ["a", "b"]);
+    /// ```
+    pub fn try_into_owned(&self) -> Result<<T as IntoOwned>::Owned, TryReserveError>
+    where
+        T: IntoOwned,
+    {
+        T::try_into_owned(self.get())
+    }
+
+    /// Returns `true` if the data deep copied out of the arena is equal to `owned`.
+    ///
+    /// This is meant for differential tests, cross-checking a value parsed into the arena
+    /// against the result of a known-good parser producing owned data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let parsed = ArenaBox::<&[&str]>::new(|arena| {
+    ///     bumpalo::collections::Vec::from_iter_in("a b".split(' '), arena).into_bump_slice()
+    /// });
+    ///
+    /// assert!(parsed.validate_against(&vec![String::from("a"), String::from("b")]));
+    /// ```
+    pub fn validate_against(&self, owned: &<T as IntoOwned>::Owned) -> bool
+    where
+        T: IntoOwned,
+        <T as IntoOwned>::Owned: PartialEq,
+    {
+        T::into_owned(self.get()) == *owned
+    }
+
+    /// Returns the data as a [`Cow`], borrowing it when the arena allocated type is its owned
+    /// counterpart and deep copying it out of the arena otherwise.
+    ///
+    /// This requires `T: IntoOwned` and `T::Owned: Clone`, so that the [`Cow`] can be turned
+    /// into an owned value. Whether the data is borrowed is decided by
+    /// [`IntoOwned::as_owned`]: it is for primitive types, and for references to them, while
+    /// types holding arena allocated strings or slices are always copied.
+    ///
+    /// This lets downstream code be written uniformly over data coming from an `ArenaBox` and
+    /// data that is already owned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let number = ArenaBox::<&u32>::new(|arena| arena.alloc(42));
+    /// assert!(matches!(number.get_cow(), Cow::Borrowed(&42)));
+    ///
+    /// let text = ArenaBox::<&str>::new(|arena| arena.alloc_str("hello"));
+    /// assert!(matches!(text.get_cow(), Cow::Owned(s) if s == "hello"));
+    /// ```
+    pub fn get_cow(&self) -> Cow<'_, <T as IntoOwned>::Owned>
+    where
+        T: IntoOwned,
+        <T as IntoOwned>::Owned: Clone,
+    {
+        let value = self.get();
+        match T::as_owned(value) {
+            Some(owned) => Cow::Borrowed(owned),
+            None => Cow::Owned(T::into_owned(value)),
+        }
+    }
+
+    /// Creates an independent deep copy of the `ArenaBox`, with its own arena.
+    ///
+    /// Unlike cloning a [`SharedArenaBox`], which shares the arena and data of the original and
+    /// cannot be mutated, the fork shares nothing with the original: it can be mutated without
+    /// affecting it, and outlives it. This requires `T: CloneInArena`, which describes how to
+    /// copy the data into the new arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let original = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["a", "b"]));
+    /// let mut fork = original.fork();
+    ///
+    /// {
+    ///     let mut handle = fork.mutate();
+    ///     *handle = handle.arena().alloc_slice_copy(&["c"]);
+    /// }
+    ///
+    /// assert_eq!(*original.get(), ["a", "b"]);
+    /// assert_eq!(*fork.get(), ["c"]);
+    /// ```
+    pub fn fork(&self) -> Self
+    where
+        T: CloneInArena,
+    {
+        ArenaBox::new(|arena| T::clone_in(self.get(), arena))
+    }
+
+    /// Moves the data of `source` into the arena of this `ArenaBox`, and combines both values.
+    ///
+    /// This deep copies the data of `source` with [`CloneInArena`], then frees the arena of
+    /// `source`, so only one arena survives, holding both values. It is the counterpart of
+    /// [`ArenaBox::new_from2`] for consolidating data into fewer arenas, at the cost of the copy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let names = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["a", "b"]));
+    /// let extra = ArenaBox::<&str>::new(|arena| arena.alloc_str("c"));
+    ///
+    /// let all = names.absorb::<&str, (&[&str], &str), _>(extra, |_, names, extra| (names, extra));
+    ///
+    /// assert_eq!(*all.get(), (&["a", "b"][..], "c"));
+    /// ```
+    pub fn absorb<U: CloneInArena, C: WithLifetime, F>(
+        self,
+        source: ArenaBox<U>,
+        link: F,
+    ) -> ArenaBox<C>
+    where
+        F: for<'a> FnOnce(
+            &'a Bump,
+            &'a <T as WithLifetime>::With<'a>,
+            <U as WithLifetime>::With<'a>,
+        ) -> <C as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new_from(self, |arena, data| {
+            link(arena, data, U::clone_in(source.get(), arena))
+        })
+    }
+
+    /// Deep copies the data into a new arena of at most `target_bytes`, freeing the old arena.
+    ///
+    /// This sets a memory budget after construction: the data is copied with
+    /// [`CloneInArena::try_clone_in`] into an arena whose allocation limit is `target_bytes`,
+    /// leaving behind the dead space accumulated by mutations and any arenas retained from
+    /// other `ArenaBox`es. [`ArenaBox::allocated_bytes`] never exceeds `target_bytes`, which
+    /// includes the bookkeeping of the chunks, so the data itself must fit in somewhat less.
+    /// The limit is lifted once the copy is done, so later mutations are not limited.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error as soon as the copy reaches the limit, in which case the partial copy
+    /// is freed and the `ArenaBox` is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the copy reaches the limit in an implementation of [`CloneInArena`] that does
+    /// not override [`CloneInArena::try_clone_in`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let mut boxed = ArenaBox::<&str>::new(|arena| arena.alloc_str("Something"));
+    /// for _ in 0..100 {
+    ///     let mut handle = boxed.mutate();
+    ///     *handle = handle.arena().alloc_str("Something different");
+    /// }
+    /// let before = boxed.allocated_bytes();
+    ///
+    /// assert!(boxed.shrink_arena_to(1).is_err());
+    /// boxed.shrink_arena_to(256).unwrap();
+    ///
+    /// assert_eq!(*boxed.get(), "Something different");
+    /// assert!(boxed.allocated_bytes() <= 256);
+    /// assert!(boxed.allocated_bytes() < before);
+    /// ```
+    pub fn shrink_arena_to(&mut self, target_bytes: usize) -> Result<(), bumpalo::AllocErr>
+    where
+        T: CloneInArena,
+    {
+        let arena = Bump::new();
+        arena.set_allocation_limit(Some(target_bytes));
+        let shrunk = Self::try_build_aligned_in(arena, 1, |arena| {
+            Ok::<_, BuildError<core::convert::Infallible>>(T::try_clone_in(self.get(), arena)?)
+        });
+        match shrunk {
+            Ok(shrunk) => {
+                shrunk.arena.set_allocation_limit(None);
+                *self = shrunk;
+                Ok(())
+            }
+            Err(BuildError::Alloc(err)) => Err(err),
+        }
+    }
+
+    /// Drops the data and builds a new value in the same arena, reset.
+    ///
+    /// Resetting the arena frees everything allocated in it, but keeps its largest chunk, so
+    /// rebuilding reuses that memory instead of allocating it again. Any arenas retained from
+    /// other `ArenaBox`es are freed. An `ArenaBox` created with `ArenaBox::from_thread_local`
+    /// keeps giving its arena back to the pool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let mut line = ArenaBox::<&str>::new(|arena| &*arena.alloc_str(&"x".repeat(4000)));
+    /// let capacity = line.allocated_bytes();
+    ///
+    /// for text in ["first", "second"] {
+    ///     line = line.reset(|arena| &*arena.alloc_str(text));
+    ///     assert_eq!(*line.get(), text);
+    /// }
+    /// assert_eq!(line.allocated_bytes(), capacity);
+    /// ```
+    pub fn reset<U: WithLifetime, F>(self, build: F) -> ArenaBox<U>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <U as WithLifetime>::With<'a>,
+    {
+        #[cfg(feature = "std")]
+        let pooled = self.pooled;
+        #[allow(unused_mut)]
+        let mut boxed = ArenaBox::build_in(self.into_reset_arena(), build);
+        #[cfg(feature = "std")]
+        {
+            boxed.pooled = pooled;
+        }
+        boxed
+    }
+
+    /// Drops the data and builds a new value in the same arena, reset, with a fallible build
+    /// closure.
+    ///
+    /// This is the fallible counterpart of [`ArenaBox::reset`], for reusing the memory of the
+    /// arena in a loop that may fail.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error returned by `build`. The `ArenaBox` is consumed either way: along with
+    /// the error, only its bare arena comes back, reset again, so it can be passed to
+    /// [`ArenaBox::new_in`] or [`ArenaBox::try_new_in`] for another attempt.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use bumpalo::Bump;
+    ///
+    /// let boxed = ArenaBox::<&[u32]>::new(|arena| &*arena.alloc_slice_copy(&[1, 2, 3]));
+    ///
+    /// fn parse<'a>(arena: &'a Bump, input: &str) -> Result<&'a [u32], std::num::ParseIntError> {
+    ///     let numbers = input.split(',').map(str::parse).collect::<Result<Vec<u32>, _>>()?;
+    ///     Ok(arena.alloc_slice_copy(&numbers))
+    /// }
+    ///
+    /// let Err((arena, _)) = boxed.try_reset::<&[u32], _, _>(|arena| parse(arena, "4,x")) else {
+    ///     panic!("Expected an error");
+    /// };
+    /// let boxed = ArenaBox::<&[u32]>::try_new_in(arena, |arena| {
+    ///     parse(arena, "4,5").map_err(BuildError::User)
+    /// })
+    /// .unwrap();
+    ///
+    /// assert_eq!(*boxed.get(), [4, 5]);
+    /// ```
+    pub fn try_reset<U: WithLifetime, E, F>(self, build: F) -> Result<ArenaBox<U>, (Bump, E)>
+    where
+        F: for<'a> FnOnce(&'a Bump) -> Result<<U as WithLifetime>::With<'a>, E>,
+    {
+        #[cfg(feature = "std")]
+        let pooled = self.pooled;
+        let arena = Box::pin(self.into_reset_arena());
+        let arena_ref: &Bump = arena.as_ref().get_ref();
+        let data = build(arena_ref).map(|value| {
+            let data_ref = arena_ref.alloc(value);
+            unsafe {
+                // SAFETY: The arena is pinned, so the pointer to the data will be valid for the lifetime of the `ArenaBox`.
+                NonNull::new_unchecked(data_ref as *mut <U as WithLifetime>::With<'_> as *mut U)
+            }
+        });
+        match data {
+            Ok(data) => {
+                #[allow(unused_mut)]
+                let mut boxed = ArenaBox::from_parts(arena, Retained::new(), data);
+                #[cfg(feature = "std")]
+                {
+                    boxed.pooled = pooled;
+                }
+                Ok(boxed)
+            }
+            Err(err) => {
+                let mut arena = *Pin::into_inner(arena);
+                arena.reset();
+                Err((arena, err))
+            }
+        }
+    }
+
+    /// Replaces the data with its default value, resetting the arena to reuse its memory.
+    ///
+    /// This recycles an `ArenaBox` in an object pool, like [`ArenaBox::reset`] does with a build
+    /// closure, without allocating a new arena. The old data is dropped before the arena is
+    /// reset, and the default value is written into the arena even if the destructor of the old
+    /// data panics, so `self` stays valid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// #[derive(Default)]
+    /// pub struct Request<'a> {
+    ///     path: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Request, pub ArenaRequest);
+    ///
+    /// let mut request = ArenaRequest::new(|arena| Request {
+    ///     path: arena.alloc_str("/index.html"),
+    /// });
+    ///
+    /// request.reset_to_default();
+    /// assert_eq!(request.get().path, "");
+    /// ```
+    pub fn reset_to_default(&mut self)
+    where
+        for<'a> <T as WithLifetime>::With<'a>: Default,
+    {
+        // Writes the default value into the arena, reset, when dropped.
+        struct Refill<'s, T: WithLifetime> {
+            boxed: &'s mut ArenaBox<T>,
+            value: Option<<T as WithLifetime>::With<'static>>,
+        }
+
+        impl<T: WithLifetime> Drop for Refill<'_, T> {
+            fn drop(&mut self) {
+                let arena = self.boxed.arena.as_mut().get_mut();
+                #[cfg(feature = "debug_validate")]
+                poison(arena);
+                arena.reset();
+                let value = self.value.take().expect("the value is only written once");
+                self.boxed.data = NonNull::from(arena.alloc(value)).cast::<T>();
+                self.boxed.built_bytes = used_bytes(arena);
+            }
+        }
+
+        let refill = Refill {
+            value: Some(Default::default()),
+            boxed: self,
+        };
+        let retained_arenas = core::mem::take(&mut refill.boxed.retained_arenas);
+        // SAFETY: The data is valid and is replaced with the default value by `refill`, even on
+        // panic, before `self` is used again. The arenas are only reset afterwards, so the
+        // destructor can still access everything the data points to.
+        unsafe {
+            core::ptr::drop_in_place(
+                refill.boxed.data.as_ptr() as *mut <T as WithLifetime>::With<'_>
+            )
+        };
+        retained_arenas.release();
+        drop(refill);
+    }
+
+    /// Writes the data within the arena to a [`core::fmt::Write`] sink using a formatting
+    /// function.
+    ///
+    /// This allows rendering the data in `no_std` environments, in a format other than its
+    /// `Display` or `Debug` implementations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use core::fmt::Write;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// let mut out = String::new();
+    /// boxed
+    ///     .write_to(&mut out, |data, w| write!(w, "msg={}", data.msg))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(out, "msg=Something");
+    /// ```
+    pub fn write_to<W, F>(&self, w: &mut W, f: F) -> core::fmt::Result
+    where
+        W: core::fmt::Write,
+        F: for<'b> FnOnce(&'b <T as WithLifetime>::With<'b>, &mut W) -> core::fmt::Result,
+    {
+        f(self.get(), w)
+    }
+
+    /// Mutates the data in the `ArenaBox`.
+    ///
+    /// This method returns a [`MutHandle`] that can be used to mutate the data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///   msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let mut boxed = ArenaData::new(|arena| Data {
+    ///    msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// assert_eq!(boxed.get().msg, "Something");
+    ///
+    /// {
+    ///    let mut handle = boxed.mutate();
+    ///    handle.msg = handle.arena().alloc_str("Something different");
+    /// }
+    ///
+    /// assert_eq!(boxed.get().msg, "Something different");
+    /// ```
+    pub fn mutate<'b>(&'b mut self) -> MutHandle<'b, T> {
+        // SAFETY: The data is guaranteed to be valid for the lifetime of the `ArenaBox`.
+        let data = unsafe { &mut *(self.data.as_ptr() as *mut <T as WithLifetime>::With<'b>) };
+        let arena = self.arena.as_ref().get_ref();
+        MutHandle {
+            data,
+            arena,
+            consumed_at_start: consumed_bytes(arena),
+        }
+    }
+
+    /// Swaps the contents of two `ArenaBox`es, without copying any data.
+    ///
+    /// Each `ArenaBox` owns its arena, so swapping the arenas along with the data keeps every
+    /// reference valid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let mut a = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("a"),
+    /// });
+    /// let mut b = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("b"),
+    /// });
+    ///
+    /// a.swap(&mut b);
+    ///
+    /// assert_eq!(a.get().msg, "b");
+    /// assert_eq!(b.get().msg, "a");
+    /// ```
+    pub fn swap(&mut self, other: &mut Self) {
+        core::mem::swap(self, other);
+    }
+
+    /// Returns the [`TypeId`](core::any::TypeId) of the type stored in the `ArenaBox`.
+    ///
+    /// This is the `TypeId` of the `'static` instantiation of the type, e.g. `Data<'static>`,
+    /// which is useful for checking what kind of box is stored in a heterogeneous collection.
+    /// It differs from [`Any::type_id`](core::any::Any::type_id) of the `ArenaBox` itself, which
+    /// is the `TypeId` of `ArenaBox<Data<'static>>`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use core::any::TypeId;
+    ///
+    /// pub struct Data<'a> {
+    ///     msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// assert_eq!(boxed.data_type_id(), TypeId::of::<Data<'static>>());
+    /// ```
+    pub fn data_type_id(&self) -> core::any::TypeId
+    where
+        T: 'static,
+    {
+        core::any::TypeId::of::<T>()
+    }
+
+    /// Returns the total capacity of the chunks allocated by the arena, in bytes.
+    pub fn allocated_bytes(&self) -> usize {
+        self.arena.allocated_bytes()
+    }
+
+    /// Returns an estimate of how much of the arena is still reachable from the data.
+    ///
+    /// The arena cannot tell which allocations are still referenced, so this returns a pair of
+    /// the bytes that were in use when the data was last built (by [`ArenaBox::new`] or one of
+    /// the transforming methods), and the bytes in use now. Everything allocated after that, for
+    /// example strings replaced through [`ArenaBox::mutate`], is potentially dead space, so a large
+    /// gap between the two is a hint that rebuilding the data in a fresh arena will free memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let mut boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str("Something"),
+    /// });
+    ///
+    /// for _ in 0..10 {
+    ///     let mut handle = boxed.mutate();
+    ///     handle.msg = handle.arena().alloc_str("Something different");
+    /// }
+    ///
+    /// let (retained, total) = boxed.retained_estimate();
+    /// assert!(retained < total);
+    /// ```
+    pub fn retained_estimate(&self) -> (usize, usize) {
+        (self.built_bytes, used_bytes(&self.arena))
+    }
+
+    /// Returns a snapshot of the memory usage of the arena.
+    ///
+    /// See [`ArenaStats`] for an example.
+    pub fn arena_stats(&self) -> ArenaStats {
+        ArenaStats {
+            allocated_bytes: self.arena.allocated_bytes(),
+            used_bytes: used_bytes(&self.arena),
+            built_bytes: self.built_bytes,
+            // SAFETY: No allocations are made while iterating and the chunks are never read.
+            chunk_count: unsafe { self.arena.iter_allocated_chunks_raw() }.count(),
+            retained_arenas: self.retained_arenas.len(),
+        }
+    }
+
+    /// Returns the layout of the chunks of the arena, as the start and the length of the bytes in
+    /// use in each chunk, newest chunk first.
+    ///
+    /// This is a diagnostic for fragmentation, like checking that the data is packed in as few
+    /// chunks as expected. The layout is up to bumpalo and may change between versions, so it
+    /// must not be relied on beyond debugging. The chunks are listed when this is called, so
+    /// allocations made in the arena afterwards are not reflected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let boxed = ArenaBox::<&str>::new(|arena| arena.alloc_str("Something"));
+    ///
+    /// let used: usize = boxed.debug_chunks().map(|(_, len)| len).sum();
+    /// assert!(used >= "Something".len());
+    /// ```
+    pub fn debug_chunks(&self) -> impl Iterator<Item = (*const u8, usize)> + use<T> {
+        // SAFETY: No allocations are made while iterating and the chunks are never read. The
+        // chunks are collected first, since the arena may be allocated into through a
+        // `Snapshot` while the iterator is alive.
+        let chunks: Vec<_> = unsafe { self.arena.iter_allocated_chunks_raw() }
+            .map(|(ptr, len)| (ptr as *const u8, len))
+            .collect();
+        chunks.into_iter()
+    }
+
+    /// Asserts that the arena has not allocated more than `max` bytes.
+    ///
+    /// This is meant for tests of transforms, to catch passes that allocate much more than
+    /// expected. The limit is checked against [`ArenaBox::allocated_bytes`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`ArenaBox::allocated_bytes`] is larger than `max`.
+    ///
+    /// # Example
+    ///
+    /// ```should_panic
+    /// # use arena_box::*;
+    ///
+    /// pub struct Data<'a> {
+    ///    msg: &'a str,
+    /// }
+    ///
+    /// make_arena_version!(Data, pub ArenaData);
+    ///
+    /// let boxed = ArenaData::new(|arena| Data {
+    ///     msg: arena.alloc_str(&"x".repeat(8192)),
+    /// });
+    ///
+    /// boxed.assert_arena_within(4096);
+    /// ```
+    #[track_caller]
+    pub fn assert_arena_within(&self, max: usize) {
+        let allocated = self.allocated_bytes();
+        assert!(
+            allocated <= max,
+            "arena allocated {allocated} bytes, more than the limit of {max} bytes"
+        );
+    }
+}
+
+impl<E> ArenaBox<bumpalo::collections::Vec<'static, E>>
+where
+    E: for<'a> WithLifetime<With<'a> = E>,
+{
+    /// Creates a new `ArenaBox` holding a growable vector with the elements of the iterator.
+    ///
+    /// The vector is allocated in the arena of the `ArenaBox`, and more elements can be pushed to
+    /// it later through [`ArenaBox::mutate`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use bumpalo::collections::Vec;
+    ///
+    /// let mut boxed = ArenaBox::<Vec<'static, u32>>::collect_vec((1..=3).map(|i| i * 10));
+    /// assert_eq!(**boxed.get(), [10, 20, 30]);
+    ///
+    /// boxed.mutate().push(40);
+    /// assert_eq!(**boxed.get(), [10, 20, 30, 40]);
+    /// ```
+    pub fn collect_vec<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+    {
+        ArenaBox::new(|arena| bumpalo::collections::Vec::from_iter_in(iter, arena))
+    }
+
+    /// Creates a new `ArenaBox` holding an empty growable vector.
+    pub fn new_vec() -> Self {
+        ArenaBox::new(|arena| bumpalo::collections::Vec::new_in(arena))
+    }
+
+    /// Appends an element to the back of the vector.
+    ///
+    /// This is a shorthand for `boxed.mutate().push(item)`.
+    pub fn push(&mut self, item: E) {
+        self.mutate().push(item);
+    }
+
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> usize {
+        self.get().len()
+    }
+
+    /// Returns `true` if the vector has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.get().is_empty()
+    }
+
+    /// Returns an iterator over the elements of the vector.
+    pub fn iter(&self) -> core::slice::Iter<'_, E> {
+        self.get().iter()
+    }
+
+    /// Returns the elements of the vector as a slice.
+    pub fn as_slice(&self) -> &[E] {
+        self.get()
+    }
+
+    /// Keeps only the elements of the vector for which the predicate returns `true`.
+    ///
+    /// The elements are removed in place, preserving the order of the others, and the buffer of
+    /// the vector keeps its capacity in the arena. This is a shorthand for
+    /// `boxed.mutate().retain(predicate)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let mut numbers = ArenaVecBox::<u32>::collect_vec(1..=6);
+    /// numbers.retain(|n| n % 3 != 0);
+    ///
+    /// assert_eq!(numbers.as_slice(), [1, 2, 4, 5]);
+    /// ```
+    pub fn retain<F>(&mut self, predicate: F)
+    where
+        F: FnMut(&E) -> bool,
+    {
+        self.mutate().retain(predicate);
+    }
+
+    /// Appends an element to the back of the vector.
+    ///
+    /// This is the same as [`ArenaBox::push`], also available as `boxed += item`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    /// use bumpalo::collections::Vec;
+    ///
+    /// let mut boxed = ArenaBox::<Vec<'static, u32>>::collect_vec([1]);
+    /// boxed.append(2);
+    /// boxed += 3;
+    ///
+    /// assert_eq!(**boxed.get(), [1, 2, 3]);
+    /// ```
+    pub fn append(&mut self, item: E) {
+        self.push(item);
+    }
+}
+
+impl<E> core::ops::AddAssign<E> for ArenaBox<bumpalo::collections::Vec<'static, E>>
+where
+    E: for<'a> WithLifetime<With<'a> = E>,
+{
+    fn add_assign(&mut self, item: E) {
+        self.append(item);
+    }
+}
+
+impl<E> ArenaBox<&'static [E]>
+where
+    E: for<'a> WithLifetime<With<'a> = E>,
+{
+    /// Creates a new `ArenaBox` holding a slice filled with the elements of an exact size
+    /// iterator.
+    ///
+    /// The slice is allocated with the exact length reported by the iterator, and filled by
+    /// pulling the elements from it, without collecting them first. For iterators that don't
+    /// know their length, `collect` into an `ArenaBox` instead, which grows the slice as needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator yields fewer elements than its reported length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let squares = ArenaBox::<&[u32]>::from_iter_exact((1..5).map(|i| i * i));
+    /// assert_eq!(*squares.get(), [1, 4, 9, 16]);
+    ///
+    /// let evens: ArenaBox<&[u32]> = (1..10).filter(|i| i % 2 == 0).collect();
+    /// assert_eq!(*evens.get(), [2, 4, 6, 8]);
+    /// ```
+    pub fn from_iter_exact<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        ArenaBox::new(|arena| &*arena.alloc_slice_fill_iter(iter))
+    }
+}
+
+/// Creates an `ArenaBox` holding a slice with the elements of the iterator.
+///
+/// The elements are collected into a growable vector in the arena, which is then turned into
+/// the slice. For exact size iterators, [`ArenaBox::from_iter_exact`] allocates the slice once.
+impl<E> FromIterator<E> for ArenaBox<&'static [E]>
+where
+    E: for<'a> WithLifetime<With<'a> = E>,
+{
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        ArenaBox::new(|arena| {
+            bumpalo::collections::Vec::from_iter_in(iter, arena).into_bump_slice()
+        })
+    }
+}
+
+impl<E: WithLifetime> ArenaBox<&'static [E]> {
+    /// Returns the number of elements in the slice.
+    pub fn len(&self) -> usize {
+        self.get().len()
+    }
+
+    /// Returns `true` if the slice has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.get().is_empty()
+    }
+
+    /// Transforms each element of the slice, allocating the new slice in the same arena.
+    ///
+    /// The closure receives the arena and a reference to each element, so the new elements can
+    /// borrow from the old ones. The old slice is not freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 20, 300]));
+    ///
+    /// let strings = numbers.map_elements::<&'static str, _>(|arena, n| arena.alloc_str(&n.to_string()));
+    ///
+    /// assert_eq!(*strings.get(), ["1", "20", "300"]);
+    /// ```
+    pub fn map_elements<N: WithLifetime, F>(self, mut f: F) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> <N as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            &*arena.alloc_slice_fill_iter(slice.iter().map(|element| f(arena, element)))
+        })
+    }
+
+    /// Transforms each element of the slice in parallel, allocating the new slice in the same
+    /// arena.
+    ///
+    /// The arena cannot be shared between threads, so the transform runs in two phases. First,
+    /// `f` is called for each element on the rayon thread pool, producing values that do not
+    /// borrow from the arena. Then, `alloc` is called for each of these values in order, on the
+    /// calling thread, to turn it into an element of the new slice, usually by allocating it in
+    /// the arena. This pays off when `f` is expensive compared to `alloc`. The old slice is not
+    /// freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let words = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["one", "two"]));
+    ///
+    /// let upper = words.map_par::<&'static str, _, _, _>(
+    ///     |word| word.to_uppercase(),
+    ///     |arena, word| arena.alloc_str(&word),
+    /// );
+    ///
+    /// assert_eq!(*upper.get(), ["ONE", "TWO"]);
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn map_par<N: WithLifetime, O, F, G>(self, f: F, mut alloc: G) -> ArenaBox<&'static [N]>
+    where
+        for<'a> <E as WithLifetime>::With<'a>: Sync,
+        O: Send,
+        F: for<'a> Fn(&'a <E as WithLifetime>::With<'a>) -> O + Sync,
+        G: for<'a> FnMut(&'a Bump, O) -> <N as WithLifetime>::With<'a>,
+    {
+        use rayon::prelude::*;
+
+        ArenaBox::new_from(self, move |arena, slice| {
+            let owned: Vec<O> = slice.par_iter().map(&f).collect();
+            &*arena.alloc_slice_fill_iter(owned.into_iter().map(|value| alloc(arena, value)))
+        })
+    }
+
+    /// Transforms each element of the slice together with its index, allocating the new slice
+    /// in the same arena.
+    ///
+    /// This is the same as [`ArenaBox::map_elements`], except that the closure also receives
+    /// the index of the element in the slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let names = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["a", "b"]));
+    ///
+    /// let ids = names.map_indexed::<(usize, &'static str), _>(|i, _, name| (i, *name));
+    ///
+    /// assert_eq!(*ids.get(), [(0, "a"), (1, "b")]);
+    /// ```
+    pub fn map_indexed<N: WithLifetime, F>(self, mut f: F) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            usize,
+            &'a Bump,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> <N as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            &*arena.alloc_slice_fill_iter(
+                slice
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element)| f(i, arena, element)),
+            )
+        })
+    }
+
+    /// Removes consecutive repeated elements, allocating the new slice in the same arena.
+    ///
+    /// This is the counterpart of [`Vec::dedup`] into a fresh allocation: the kept elements are
+    /// cloned into the new slice, and the old slice is not freed and stays in the arena. It
+    /// removes all duplicates if the slice is sorted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let ids = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 1, 2, 3, 3, 3, 1]));
+    ///
+    /// let ids = ids.map_dedup();
+    ///
+    /// assert_eq!(*ids.get(), [1, 2, 3, 1]);
+    /// ```
+    pub fn map_dedup(self) -> Self
+    where
+        for<'a> <E as WithLifetime>::With<'a>: PartialEq + Clone,
+    {
+        ArenaBox::new_from(self, |arena, slice| {
+            bumpalo::collections::Vec::from_iter_in(
+                slice
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, element)| *i == 0 || slice[i - 1] != **element)
+                    .map(|(_, element)| element.clone()),
+                arena,
+            )
+            .into_bump_slice()
+        })
+    }
+
+    /// Groups consecutive elements with equal keys, allocating the slice of groups in the same
+    /// arena.
+    ///
+    /// This is the counterpart of [`slice::chunk_by`] with a key function: each group is a pair
+    /// of the key and a subslice of the old slice, which stays in the arena, so the elements are
+    /// not copied. Like `chunk_by`, only consecutive elements are grouped, so a sorted slice
+    /// gives a single group per key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let words = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["a", "an", "at", "be"]));
+    ///
+    /// let groups = words.map_group_by::<char, _>(|word| word.chars().next().unwrap());
+    ///
+    /// assert_eq!(*groups.get(), [('a', &["a", "an", "at"][..]), ('b', &["be"][..])]);
+    /// ```
+    pub fn map_group_by<K: WithLifetime, F>(
+        self,
+        mut key: F,
+    ) -> ArenaBox<&'static [(K, &'static [E])]>
+    where
+        for<'a> <K as WithLifetime>::With<'a>: PartialEq,
+        F: for<'a> FnMut(&'a <E as WithLifetime>::With<'a>) -> <K as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            let mut groups = bumpalo::collections::Vec::new_in(arena);
+            let mut current: Option<(<K as WithLifetime>::With<'_>, usize)> = None;
+            for (i, element) in slice.iter().enumerate() {
+                let element_key = key(element);
+                match current.take() {
+                    Some((group_key, start)) if group_key == element_key => {
+                        current = Some((group_key, start));
+                    }
+                    Some((group_key, start)) => {
+                        groups.push((group_key, &slice[start..i]));
+                        current = Some((element_key, i));
+                    }
+                    None => current = Some((element_key, i)),
+                }
+            }
+            if let Some((group_key, start)) = current {
+                groups.push((group_key, &slice[start..]));
+            }
+            groups.into_bump_slice()
+        })
+    }
+
+    /// Splits the slice into the elements for which the predicate returns `true` and the others,
+    /// allocating both new slices in the same arena.
+    ///
+    /// This is the counterpart of [`Iterator::partition`]: the elements are cloned into the new
+    /// slices, keeping their order, and the old slice is not freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 2, 3, 4, 5]));
+    ///
+    /// let parts = numbers.map_partition(|n| n % 2 == 0);
+    ///
+    /// assert_eq!(*parts.get(), (&[2, 4][..], &[1, 3, 5][..]));
+    /// ```
+    pub fn map_partition<F>(self, mut predicate: F) -> ArenaBox<(&'static [E], &'static [E])>
+    where
+        for<'a> <E as WithLifetime>::With<'a>: Clone,
+        F: for<'a> FnMut(&'a <E as WithLifetime>::With<'a>) -> bool,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            let mut matching = bumpalo::collections::Vec::new_in(arena);
+            let mut rest = bumpalo::collections::Vec::new_in(arena);
+            for element in slice.iter() {
+                if predicate(element) {
+                    matching.push(element.clone());
+                } else {
+                    rest.push(element.clone());
+                }
+            }
+            (matching.into_bump_slice(), rest.into_bump_slice())
+        })
+    }
+
+    /// Transforms each element of the slice, allocating the new slice in reverse order in the same
+    /// arena.
+    ///
+    /// To only reverse the slice, the closure can copy the elements as they are. The old slice
+    /// is not freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 2, 3]));
+    ///
+    /// let reversed = numbers.map_rev::<u32, _>(|_, n| n * 10);
+    ///
+    /// assert_eq!(*reversed.get(), [30, 20, 10]);
+    /// ```
+    pub fn map_rev<N: WithLifetime, F>(self, mut f: F) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> <N as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            &*arena.alloc_slice_fill_iter(slice.iter().rev().map(|element| f(arena, element)))
+        })
+    }
+
+    /// Transforms each sliding window of `SIZE` consecutive elements of the slice, allocating
+    /// the new slice in the same arena.
+    ///
+    /// The windows overlap, so for a slice of length `len` the new slice has `len - SIZE + 1`
+    /// elements, like n-grams over a sequence. It is empty if the slice is shorter than `SIZE`.
+    /// The old slice is not freed and stays in the arena.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `SIZE` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 2, 3, 4]));
+    ///
+    /// let sums = numbers.map_windows::<3, u32, _>(|_, [a, b, c]| a + b + c);
+    ///
+    /// assert_eq!(*sums.get(), [6, 9]);
+    /// ```
+    pub fn map_windows<const SIZE: usize, N: WithLifetime, F>(
+        self,
+        mut f: F,
+    ) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a [<E as WithLifetime>::With<'a>; SIZE],
+        ) -> <N as WithLifetime>::With<'a>,
+    {
+        assert!(SIZE != 0, "window size must be non-zero");
+        ArenaBox::new_from(self, move |arena, slice| {
+            &*arena.alloc_slice_fill_iter(slice.windows(SIZE).map(|window| {
+                f(
+                    arena,
+                    window.try_into().expect("windows have `SIZE` elements"),
+                )
+            }))
+        })
+    }
+
+    /// Transforms each element of the slice together with its index and the previous output
+    /// element, allocating the new slice in the same arena.
+    ///
+    /// This is a scan over the slice, for transforms that depend on the previous result like
+    /// delta decoding or running sums. The closure receives `None` as the previous output for the
+    /// first element. The old slice is not freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let deltas = ArenaBox::<&[i32]>::new(|arena| arena.alloc_slice_copy(&[10, 2, -5, 1]));
+    ///
+    /// let values =
+    ///     deltas.map_with_index_and_prev::<i32, _>(|_, _, prev, delta| prev.unwrap_or(&0) + delta);
+    ///
+    /// assert_eq!(*values.get(), [10, 12, 7, 8]);
+    /// ```
+    pub fn map_with_index_and_prev<N: WithLifetime, F>(self, mut f: F) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            usize,
+            &'a Bump,
+            Option<&<N as WithLifetime>::With<'a>>,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> <N as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            let mut out = bumpalo::collections::Vec::with_capacity_in(slice.len(), arena);
+            for (i, element) in slice.iter().enumerate() {
+                let next = f(i, arena, out.last(), element);
+                out.push(next);
+            }
+            out.into_bump_slice()
+        })
+    }
+
+    /// Transforms the elements of the slice, keeping only those for which the closure returns
+    /// `Some`, and allocates the new slice in the same arena.
+    ///
+    /// This is the `ArenaBox` counterpart of [`Iterator::filter_map`], useful for pruning
+    /// elements during a pass. The old slice is not freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let tokens = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["1", "x", "3"]));
+    ///
+    /// let numbers = tokens.map_filter::<u32, _>(|_, token| token.parse().ok());
+    ///
+    /// assert_eq!(*numbers.get(), [1, 3]);
+    /// ```
+    pub fn map_filter<N: WithLifetime, F>(self, mut f: F) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> Option<<N as WithLifetime>::With<'a>>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            bumpalo::collections::Vec::from_iter_in(
+                slice.iter().filter_map(|element| f(arena, element)),
+                arena,
+            )
+            .into_bump_slice()
+        })
+    }
+
+    /// Transforms the elements of the slice in chunks of `chunk_size`, allocating the new slice
+    /// in the same arena.
+    ///
+    /// The closure sees a window of up to `chunk_size` elements at a time, and pushes its outputs
+    /// for that chunk to the output vector, which is then turned into the new slice. This suits
+    /// batch oriented or vectorizable transforms. All chunks have `chunk_size` elements except
+    /// possibly the last one, which has the remainder, and each chunk may produce any number of
+    /// outputs. The old slice is not freed and stays in the arena.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let samples = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 2, 3, 4, 5]));
+    ///
+    /// let sums = samples.map_chunked::<u32, _>(2, |_, chunk, out| out.push(chunk.iter().sum()));
+    ///
+    /// assert_eq!(*sums.get(), [3, 7, 5]);
+    /// ```
+    pub fn map_chunked<N: WithLifetime, F>(
+        self,
+        chunk_size: usize,
+        mut f: F,
+    ) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a [<E as WithLifetime>::With<'a>],
+            &mut bumpalo::collections::Vec<'a, <N as WithLifetime>::With<'a>>,
+        ),
+    {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        ArenaBox::new_from(self, move |arena, slice| {
+            let mut out = bumpalo::collections::Vec::with_capacity_in(slice.len(), arena);
+            for chunk in slice.chunks(chunk_size) {
+                f(arena, chunk, &mut out);
+            }
+            out.into_bump_slice()
+        })
+    }
+
+    /// Transforms the elements of the slice until the closure returns `None`, allocating the new
+    /// slice of the transformed prefix in the same arena.
+    ///
+    /// This is the `ArenaBox` counterpart of [`Iterator::map_while`]: the elements after the
+    /// first one for which the closure returns `None` are not visited. The old slice is not
+    /// freed and stays in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let tokens = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["1", "2", "x", "4"]));
+    ///
+    /// let numbers = tokens.map_while::<u32, _>(|_, token| token.parse().ok());
+    ///
+    /// assert_eq!(*numbers.get(), [1, 2]);
+    /// ```
+    pub fn map_while<N: WithLifetime, F>(self, mut f: F) -> ArenaBox<&'static [N]>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> Option<<N as WithLifetime>::With<'a>>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            bumpalo::collections::Vec::from_iter_in(
+                slice.iter().map_while(|element| f(arena, element)),
+                arena,
+            )
+            .into_bump_slice()
+        })
+    }
+
+    /// Transforms each element of the slice with a fallible closure, allocating the new slice in
+    /// the same arena.
+    ///
+    /// If the closure fails for any element, the transform stops and the original `ArenaBox` is
+    /// returned together with the error, so nothing is lost. The elements transformed before the
+    /// failure are not freed and stay in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let words = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["1", "2", "x"]));
+    ///
+    /// let Err((words, err)) = words.map_fallible_elements::<u32, _, _>(|_, w| w.parse()) else {
+    ///     panic!("Expected an error");
+    /// };
+    /// assert_eq!(err.to_string(), "invalid digit found in string");
+    /// assert_eq!(*words.get(), ["1", "2", "x"]);
+    /// ```
+    pub fn map_fallible_elements<N: WithLifetime, Err, F>(
+        self,
+        mut f: F,
+    ) -> Result<ArenaBox<&'static [N]>, (Self, Err)>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> Result<<N as WithLifetime>::With<'a>, Err>,
+    {
+        let mapped = {
+            let arena_ref = self.arena.as_ref().get_ref();
+            let slice = self.get();
+            let mut mapped = bumpalo::collections::Vec::with_capacity_in(slice.len(), arena_ref);
+            slice
+                .iter()
+                .try_for_each(|element| {
+                    mapped.push(f(arena_ref, element)?);
+                    Ok(())
+                })
+                .map(|()| {
+                    let mapped_ref = arena_ref.alloc(mapped.into_bump_slice());
+                    unsafe {
+                        NonNull::new_unchecked(
+                            mapped_ref as *mut <&'static [N] as WithLifetime>::With<'_>
+                                as *mut &'static [N],
+                        )
+                    }
+                })
+        };
+        match mapped {
+            Ok(mapped) => {
+                let (arena, retained_arenas, _) = self.into_parts();
+                Ok(ArenaBox::from_parts(arena, retained_arenas, mapped))
+            }
+            Err(err) => Err((self, err)),
+        }
+    }
+
+    /// Transforms each element of the slice with a fallible closure, reporting the index of the
+    /// element that failed.
+    ///
+    /// This is [`ArenaBox::map_fallible_elements`] for validation passes that need the
+    /// position of the offending element: on failure, the original `ArenaBox` is returned
+    /// together with the index of the element and the error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let words = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["1", "x", "3"]));
+    ///
+    /// let Err((words, index, _)) = words.try_map_elements::<u32, _, _>(|_, w| w.parse()) else {
+    ///     panic!("Expected an error");
+    /// };
+    /// assert_eq!(index, 1);
+    /// assert_eq!(words.get()[index], "x");
+    /// ```
+    pub fn try_map_elements<N: WithLifetime, Err, F>(
+        self,
+        mut f: F,
+    ) -> Result<ArenaBox<&'static [N]>, (Self, usize, Err)>
+    where
+        F: for<'a> FnMut(
+            &'a Bump,
+            &'a <E as WithLifetime>::With<'a>,
+        ) -> Result<<N as WithLifetime>::With<'a>, Err>,
+    {
+        let mut index = 0;
+        self.map_fallible_elements::<N, _, _>(|arena, element| {
+            let mapped = f(arena, element).map_err(|err| (index, err));
+            index += 1;
+            mapped
+        })
+        .map_err(|(boxed, (index, err))| (boxed, index, err))
+    }
+
+    /// Inserts an item into a sorted slice, keeping it sorted.
+    ///
+    /// A new slice with the item inserted after any equal elements is allocated in the arena of
+    /// the `ArenaBox`. The old slice is not freed and becomes dead space in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let boxed = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 3, 5]));
+    ///
+    /// let boxed = boxed.insert_sorted(4).insert_sorted(0);
+    ///
+    /// assert_eq!(*boxed.get(), [0, 1, 3, 4, 5]);
+    /// ```
+    pub fn insert_sorted(self, item: E) -> Self
+    where
+        E: Ord + Clone + for<'a> WithLifetime<With<'a> = E>,
+    {
+        ArenaBox::new_from(self, move |arena, slice| {
+            let index = slice.partition_point(|element| *element <= item);
+            let mut item = Some(item);
+            &*arena.alloc_slice_fill_with(slice.len() + 1, |i| match i.cmp(&index) {
+                core::cmp::Ordering::Less => slice[i].clone(),
+                core::cmp::Ordering::Equal => item.take().unwrap(),
+                core::cmp::Ordering::Greater => slice[i - 1].clone(),
+            })
+        })
+    }
+
+    /// Copies the elements of the slice into a `Vec`.
+    ///
+    /// This requires elements that do not borrow from the arena. For elements that do, like
+    /// strings, [`ArenaBox::into_owned`] deep copies them into their owned counterparts instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let boxed = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 2, 3]));
+    ///
+    /// let numbers: Vec<u32> = boxed.to_vec();
+    /// assert_eq!(numbers, [1, 2, 3]);
+    /// ```
+    pub fn to_vec(&self) -> Vec<E>
+    where
+        E: Clone + for<'a> WithLifetime<With<'a> = E>,
+    {
+        self.get().to_vec()
+    }
+}
+
+impl<E: WithLifetime> ArenaBox<&'static [&'static [E]]> {
+    /// Flattens the slice of slices into a single slice, allocated in the same arena.
+    ///
+    /// The total length is computed first, so the new slice is allocated once, and the elements
+    /// are cloned into it. The old slices are not freed and stay in the arena.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let words = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["ab", "", "c"]));
+    ///
+    /// let chars = words.map_elements::<&[char], _>(|arena, word| {
+    ///     arena.alloc_slice_fill_iter(word.chars().collect::<Vec<_>>())
+    /// });
+    /// let flat = chars.map_flatten();
+    ///
+    /// assert_eq!(*flat.get(), ['a', 'b', 'c']);
+    /// ```
+    pub fn map_flatten(self) -> ArenaBox<&'static [E]>
+    where
+        for<'a> <E as WithLifetime>::With<'a>: Clone,
+    {
+        ArenaBox::new_from(self, |arena, slices| {
+            let len = slices.iter().map(|slice| slice.len()).sum();
+            let mut flat = bumpalo::collections::Vec::with_capacity_in(len, arena);
+            for slice in slices.iter() {
+                flat.extend(slice.iter().cloned());
+            }
+            flat.into_bump_slice()
+        })
+    }
+}
+
+impl ArenaBox<&'static [u8]> {
+    /// Returns the byte buffer held by the `ArenaBox`.
+    ///
+    /// This is a shorthand for [`ArenaBox::get`] for boxes owning binary data, like the contents
+    /// of a file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use arena_box::*;
+    ///
+    /// let buffer = ArenaBox::<&[u8]>::new(|arena| arena.alloc_slice_copy(b"\x7fELF"));
+    ///
+    /// assert_eq!(buffer.as_bytes(), b"\x7fELF");
+    /// assert_eq!(buffer.len(), 4);
+    /// assert!(!buffer.is_empty());
+    /// ```
+    pub fn as_bytes(&self) -> &[u8] {
+        self.get()
+    }
+}
+
+/// Creates an `ArenaBox` holding the default value of the data.
+///
+/// This is available when the data implements `Default` for any lifetime of the arena, which
+/// is the case for slices, and for structs deriving `Default` whose string fields default to
+/// `""`. No allocation is needed for such values, so only the smallest chunk bumpalo allocates
+/// is used, to hold the value itself.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// #[derive(Default)]
+/// pub struct MyError<'a> {
+///     message: &'a str,
+///     details: &'a str,
+/// }
+///
+/// make_arena_version!(MyError, pub ArenaMyError);
+///
+/// let error = ArenaMyError::default();
+/// assert_eq!(error.get().message, "");
+///
+/// let boxed = ArenaBox::<&[u32]>::default();
+/// assert!(boxed.get().is_empty());
+/// ```
+impl<T: WithLifetime> Default for ArenaBox<T>
+where
+    for<'a> T::With<'a>: Default,
+{
+    fn default() -> Self {
+        ArenaBox::new(|_| Default::default())
+    }
+}
+
+/// The byte pattern written over the arena memory when an `ArenaBox` is dropped.
+#[cfg(feature = "debug_validate")]
+pub const POISON_BYTE: u8 = 0xA5;
+
+/// Overwrites all the memory in use in the arena with [`POISON_BYTE`].
+#[cfg(feature = "debug_validate")]
+fn poison(arena: &mut Bump) {
+    // SAFETY: The arena is exclusively borrowed and nothing allocated in it is used again.
+    for (ptr, len) in unsafe { arena.iter_allocated_chunks_raw() } {
+        unsafe { ptr.write_bytes(POISON_BYTE, len) };
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    // An arena kept around to be reused by `ArenaBox::from_thread_local`.
+    static POOLED_ARENA: core::cell::Cell<Option<Bump>> = const { core::cell::Cell::new(None) };
+}
+
+impl<T: WithLifetime> Drop for ArenaBox<T> {
+    fn drop(&mut self) {
+        // SAFETY: The data is valid and is never used again. The arena is only freed afterwards,
+        // so the destructor can still access everything the data points to.
+        unsafe {
+            core::ptr::drop_in_place(self.data.as_ptr() as *mut <T as WithLifetime>::With<'_>)
+        };
+        #[cfg(feature = "debug_validate")]
+        {
+            poison(self.arena.as_mut().get_mut());
+            for arena in self.retained_arenas.arenas_mut() {
+                poison(arena.as_mut().get_mut());
+            }
+        }
+        #[cfg(feature = "std")]
+        if self.pooled {
+            // Nothing borrows from the arena anymore, so it can be moved out and reset. Fails
+            // only if the thread is exiting, in which case the arena is just freed.
+            let mut arena = core::mem::take(self.arena.as_mut().get_mut());
+            arena.reset();
+            let _ = POOLED_ARENA.try_with(|pool| pool.set(Some(arena)));
+        }
+    }
+}
+
+impl<T: core::fmt::Display + WithLifetime> core::fmt::Display for ArenaBox<T>
+where
+    for<'a> T::With<'a>: core::fmt::Display,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(self.get(), f)
+    }
+}
+
+impl<T: core::fmt::Debug + WithLifetime> core::fmt::Debug for ArenaBox<T>
+where
+    for<'a> T::With<'a>: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(self.get(), f)
+    }
+}
+
+impl<T: WithLifetime> PartialEq for ArenaBox<T>
+where
+    for<'a> T::With<'a>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data || self.get() == other.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Data<'a> {
+        msg: &'a str,
+    }
+    make_arena_version!(Data, ArenaData);
+
+    #[test]
+    fn test_build() {
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("Something"),
+        });
+        assert_eq!(boxed.get().msg, "Something");
+    }
+
+    #[test]
+    fn test_with_min_align() {
+        for align in [1, 32, 64, 4096] {
+            let boxed = ArenaData::with_min_align(align, |arena| Data {
+                msg: arena.alloc_str("Something"),
+            });
+            assert_eq!(boxed.get().msg, "Something");
+            assert_eq!(boxed.data.as_ptr() as usize % align, 0);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "alignment must be a power of two")]
+    fn test_with_min_align_not_power_of_two() {
+        ArenaData::with_min_align(24, |arena| Data {
+            msg: arena.alloc_str("Something"),
+        });
+    }
+
+    #[test]
+    fn test_build_panic() {
+        extern crate std;
+
+        let result = std::panic::catch_unwind(|| {
+            ArenaData::new(|arena| {
+                arena.alloc_str("half built");
+                panic!("build failed");
+            })
+        });
+        assert!(result.is_err());
+
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        let result = std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| {
+            boxed.map_with_scratch(|_, _, _| panic!("transform failed"))
+        }));
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_reader() {
+        // Larger than the read chunk size, to exercise several reads.
+        let text = "word ".repeat(4 * 1024);
+        let boxed =
+            ArenaBox::<&[&str]>::from_reader(std::io::Cursor::new(&text), |arena, input| {
+                let input = core::str::from_utf8(input).unwrap();
+                bumpalo::collections::Vec::from_iter_in(input.split_whitespace(), arena)
+                    .into_bump_slice()
+            })
+            .unwrap();
+        assert_eq!(boxed.get().len(), 4 * 1024);
+        assert!(boxed.get().iter().all(|word| *word == "word"));
+
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+        }
+        let Err(err) = ArenaBox::<&[u8]>::from_reader(Failing, |_, input| input) else {
+            panic!("Expected an error");
+        };
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_new_saturating() {
+        let long = "x".repeat(8 * 1024);
+        let boxed = ArenaAggregate::new_saturating(4096, |arena| Aggregate {
+            parts: &[],
+            joined: arena.alloc_str(&long),
+        });
+        assert_eq!(boxed.get().joined, "");
+        assert!(boxed.allocated_bytes() <= 4096);
+
+        let boxed = ArenaBox::<&[u8]>::new_saturating(4096, |arena| {
+            let small = arena.alloc_slice_copy(b"small");
+            assert!(arena.alloc_slice_copy(long.as_bytes()).is_empty());
+            small
+        });
+        assert_eq!(*boxed.get(), b"small");
+
+        // The limit is gone after construction.
+        let mut boxed = boxed;
+        let handle = boxed.mutate();
+        assert_eq!(handle.arena().alloc_str(&long).len(), long.len());
+    }
+
+    #[test]
+    fn test_from_owned() {
+        let boxed = ArenaBox::<(u32, &str)>::from_owned((7, "static"));
+        assert_eq!(boxed.get().0, 7);
+        assert_eq!(boxed.get().1, "static");
+
+        let mut boxed = ArenaData::from_owned(Data { msg: "static" });
+        {
+            let mut handle = boxed.mutate();
+            handle.msg = handle.arena().alloc_str("arena");
+        }
+        assert_eq!(boxed.get().msg, "arena");
+    }
+
+    #[test]
+    fn test_new_configured() {
+        let boxed = ArenaData::new_configured(BumpConfig::default(), |arena| Data {
+            msg: arena.alloc_str("default"),
+        });
+        assert_eq!(boxed.get().msg, "default");
+
+        let config = BumpConfig::new().chunk_capacity(8192).min_align(32);
+        let boxed = ArenaData::new_configured(config, |arena| Data {
+            msg: arena.alloc_str("aligned"),
+        });
+        assert!(boxed.allocated_bytes() >= 8192);
+        assert_eq!(boxed.get() as *const Data as usize % 32, 0);
+
+        // The limit stays in place after construction.
+        let config = BumpConfig::new().allocation_limit(4096);
+        let mut boxed = ArenaData::new_configured(config, |arena| Data {
+            msg: arena.alloc_str("limited"),
+        });
+        let handle = boxed.mutate();
+        assert!(handle.arena().try_alloc_str(&"x".repeat(8192)).is_err());
+    }
+
+    #[test]
+    fn test_try_new_configured() {
+        let config = BumpConfig::new().allocation_limit(4096).min_align(16);
+        let boxed = ArenaData::try_new_configured::<(), _>(config, |arena| {
+            Ok(Data {
+                msg: arena.try_alloc_str("limited")?,
+            })
+        })
+        .unwrap();
+        assert_eq!(boxed.get().msg, "limited");
+        assert_eq!(boxed.get() as *const Data as usize % 16, 0);
+
+        let too_large = ArenaData::try_new_configured(config, |arena| {
+            Ok(Data {
+                msg: arena.try_alloc_str(&"x".repeat(8192))?,
+            })
+        });
+        assert!(matches!(too_large, Err(BuildError::<&str>::Alloc(_))));
+
+        let rejected = ArenaData::try_new_configured(config, |_| Err(BuildError::User("rejected")));
+        assert_eq!(rejected.err(), Some(BuildError::User("rejected")));
+
+        // Allocating the value itself fails instead of panicking.
+        let no_room =
+            ArenaData::try_new_configured::<(), _>(BumpConfig::new().allocation_limit(0), |_| {
+                Ok(Data { msg: "static" })
+            });
+        assert!(matches!(no_room, Err(BuildError::Alloc(_))));
+    }
+
+    #[test]
+    fn test_new_traced() {
+        let mut total = 0;
+        let mut labels = Vec::new();
+        let boxed = ArenaAggregate::new_traced(
+            |size, label| {
+                total += size;
+                labels.extend(label.map(alloc::string::String::from));
+            },
+            |arena| {
+                let parts = arena.labeled("parts");
+                Aggregate {
+                    parts: parts.alloc_slice_fill_iter([
+                        &*parts.alloc(Data {
+                            msg: parts.alloc_str("a"),
+                        }),
+                        &*arena.alloc(Data {
+                            msg: arena.arena().alloc_str("untraced"),
+                        }),
+                    ]),
+                    joined: arena.alloc_str("a-untraced"),
+                }
+            },
+        );
+        assert_eq!(boxed.get().parts[1].msg, "untraced");
+        assert_eq!(
+            total,
+            1 + 2 * size_of::<&Data>() + 2 * size_of::<Data>() + 10
+        );
+        assert_eq!(labels, ["parts", "parts", "parts"]);
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let long = "x".repeat(8 * 1024);
+
+        let boxed = ArenaData::try_new::<(), _>(|arena| {
+            Ok(Data {
+                msg: arena.try_alloc_str("ok")?,
+            })
+        });
+        assert_eq!(boxed.unwrap().get().msg, "ok");
+
+        let result = ArenaData::with_allocation_limit::<(), _>(4096, |arena| {
+            Ok(Data {
+                msg: arena.try_alloc_str(&long)?,
+            })
+        });
+        assert_eq!(result.err(), Some(BuildError::Alloc(bumpalo::AllocErr)));
+
+        let source = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("source"),
+        });
+        let result = ArenaData::try_new_from(source, |_, data| {
+            Err::<Data, _>(BuildError::User(data.msg.len()))
+        });
+        assert_eq!(result.err(), Some(BuildError::User(6)));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_from_mmap() {
+        let path = std::env::temp_dir().join("arena_box_test_from_mmap.txt");
+        std::fs::write(&path, "alpha beta").unwrap();
+
+        // SAFETY: The file is not modified while it is mapped.
+        let words = unsafe {
+            ArenaBox::<&[&[u8]]>::from_mmap(&path, |arena, bytes| {
+                arena.alloc_slice_fill_iter(bytes.split(|&b| b == b' ').collect::<Vec<_>>())
+            })
+        }
+        .unwrap();
+        // The words borrow from the mapping, not from the arena.
+        let word = words.get()[1].as_ptr() as usize;
+        assert!(
+            words
+                .debug_chunks()
+                .all(|(start, len)| !(start as usize..start as usize + len).contains(&word))
+        );
+
+        // The mapping moves along with the arena to the derived `ArenaBox`.
+        let lengths = words.map_elements::<usize, _>(|_, word| word.len());
+        assert_eq!(*lengths.get(), [5, 4]);
+        let joined = ArenaBox::<&str>::new_from(lengths, |arena, _| arena.alloc_str("ok"));
+        assert_eq!(joined.retained_arenas.mmaps.len(), 1);
+
+        drop(joined);
+        std::fs::remove_file(&path).unwrap();
+        assert!(unsafe { ArenaBox::<&[u8]>::from_mmap(&path, |_, bytes| bytes) }.is_err());
+    }
+
+    #[test]
+    fn test_new_borrowing() {
+        static WORDS: [&str; 3] = ["one", "two", "three"];
+
+        let boxed = ArenaBox::<(&[&str], &str)>::new_borrowing(&WORDS, |arena, words| {
+            (&words[1..], &*arena.alloc_str(&words.concat()))
+        });
+        assert!(core::ptr::eq(boxed.get().0, &WORDS[1..]));
+        assert_eq!(boxed.get().1, "onetwothree");
+    }
+
+    #[test]
+    fn test_new_in() {
+        let mut arena = Bump::with_capacity(4096);
+        arena.alloc_str(&"x".repeat(4000));
+        arena.reset();
+        let capacity = arena.allocated_bytes();
+
+        let boxed = ArenaData::new_in(arena, |arena| Data {
+            msg: arena.alloc_str(&"y".repeat(4000)),
+        });
+        assert_eq!(boxed.allocated_bytes(), capacity);
+
+        let result = ArenaData::try_new_in(Bump::new(), |arena| {
+            let msg = arena.try_alloc_str("not a number")?;
+            msg.parse::<u32>().map_err(BuildError::User)?;
+            Ok(Data { msg })
+        });
+        assert!(matches!(result, Err(BuildError::User(_))));
+    }
+
+    #[test]
+    fn test_try_reset() {
+        use alloc::rc::Rc;
+
+        struct Owned(Rc<()>);
+        impl WithLifetime for Owned {
+            type With<'a> = Owned;
+        }
+
+        let rc = Rc::new(());
+        let boxed = ArenaBox::<(&str, Owned)>::new(|arena| {
+            (&*arena.alloc_str(&"x".repeat(4000)), Owned(rc.clone()))
+        });
+        assert!(Rc::ptr_eq(&boxed.get().1.0, &rc));
+        let capacity = boxed.allocated_bytes();
+
+        let boxed: ArenaData = boxed.reset(|arena| Data {
+            msg: arena.alloc_str(&"y".repeat(4000)),
+        });
+        assert_eq!(Rc::strong_count(&rc), 1);
+        assert_eq!(boxed.allocated_bytes(), capacity);
+
+        let boxed: ArenaData = boxed
+            .try_reset(|arena| {
+                Ok::<_, ()>(Data {
+                    msg: arena.alloc_str("z"),
+                })
+            })
+            .unwrap();
+        assert_eq!(boxed.get().msg, "z");
+
+        let Err((arena, err)) = boxed.try_reset::<Data<'_>, _, _>(|arena| {
+            arena.alloc_str(&"w".repeat(4000));
+            Err("failed")
+        }) else {
+            panic!("Expected an error");
+        };
+        assert_eq!(err, "failed");
+        assert_eq!(arena.allocated_bytes(), capacity);
+        assert_eq!(used_bytes(&arena), 0);
+    }
+
+    #[test]
+    fn test_reset_to_default() {
+        #[derive(Default)]
+        struct Pooled<'a> {
+            name: &'a str,
+            items: &'a [u32],
+        }
+        make_arena_version!(Pooled, ArenaPooled);
+
+        let mut pooled = ArenaPooled::new(|arena| Pooled {
+            name: arena.alloc_str(&"x".repeat(4000)),
+            items: arena.alloc_slice_copy(&[1, 2, 3]),
+        });
+        let allocated = pooled.allocated_bytes();
+
+        // Only the largest chunk of the arena is kept.
+        pooled.reset_to_default();
+        assert_eq!(pooled.get().name, "");
+        assert!(pooled.get().items.is_empty());
+        let capacity = pooled.allocated_bytes();
+        assert!(capacity > 4000 && capacity <= allocated);
+
+        {
+            let mut handle = pooled.mutate();
+            handle.name = handle.arena().alloc_str(&"y".repeat(4000));
+        }
+        pooled.reset_to_default();
+        assert_eq!(pooled.get().name, "");
+        assert_eq!(pooled.allocated_bytes(), capacity);
+
+        // The arena is reset in place rather than replaced, and keeps its chunk.
+        let arena: *const Bump = &*pooled.arena;
+        let chunks: Vec<_> = pooled.debug_chunks().collect();
+        for _ in 0..3 {
+            pooled.reset_to_default();
+            assert!(core::ptr::eq(&*pooled.arena, arena));
+            assert!(
+                pooled
+                    .debug_chunks()
+                    .map(|(ptr, _)| ptr)
+                    .eq(chunks.iter().map(|c| c.0))
+            );
+        }
+    }
+
+    #[test]
+    fn test_reset_to_default_panicking_drop() {
+        extern crate std;
+
+        #[derive(Default)]
+        struct Bomb<'a> {
+            msg: &'a str,
+            armed: bool,
+        }
+        impl Drop for Bomb<'_> {
+            fn drop(&mut self) {
+                if self.armed {
+                    panic!("destructor failed");
+                }
+            }
+        }
+        make_arena_version!(Bomb, ArenaBomb);
+
+        let mut boxed = ArenaBomb::new(|arena| Bomb {
+            msg: arena.alloc_str("armed"),
+            armed: true,
+        });
+        let result =
+            std::panic::catch_unwind(core::panic::AssertUnwindSafe(|| boxed.reset_to_default()));
+        assert!(result.is_err());
+        // The default value was written despite the panic.
+        assert_eq!(boxed.get().msg, "");
+        assert!(!boxed.get().armed);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_thread_local() {
+        let big = ArenaData::from_thread_local(|arena| Data {
+            msg: arena.alloc_str(&"x".repeat(64 * 1024)),
+        });
+        let allocated = big.allocated_bytes();
+        assert!(allocated > 64 * 1024);
+
+        // While the pooled arena is in use, another box gets a fresh one.
+        let other = ArenaData::from_thread_local(|arena| Data {
+            msg: arena.alloc_str("other"),
+        });
+        assert!(other.allocated_bytes() < allocated);
+        drop(other);
+        drop(big);
+
+        // The largest chunk of the first arena is reused.
+        let small = ArenaData::from_thread_local(|arena| Data {
+            msg: arena.alloc_str("small"),
+        });
+        assert_eq!(small.get().msg, "small");
+        assert!(small.allocated_bytes() > 64 * 1024);
+
+        // Boxes built from a pooled box don't return the arena to the pool.
+        let derived = ArenaAugmentedData::new_from(small, |arena, data| AugmentedData {
+            data,
+            extra: arena.alloc_str("extra"),
+        });
+        drop(derived);
+        let fresh = ArenaData::from_thread_local(|arena| Data {
+            msg: arena.alloc_str("fresh"),
+        });
+        assert!(fresh.allocated_bytes() < allocated);
+    }
+
+    #[test]
+    fn test_with_chunk_capacity() {
+        fn build(arena: &Bump) -> Data<'_> {
+            let mut msg = "";
+            for _ in 0..64 {
+                msg = arena.alloc_str(&"x".repeat(100));
+            }
+            Data { msg }
+        }
+        fn chunk_count<T: WithLifetime>(boxed: &ArenaBox<T>) -> usize {
+            unsafe { boxed.arena.iter_allocated_chunks_raw() }.count()
+        }
+
+        let default = ArenaData::new(build);
+        let tuned = ArenaData::with_chunk_capacity(8 * 1024, build);
+        assert_eq!(chunk_count(&tuned), 1);
+        assert!(chunk_count(&default) > 1);
+        assert_eq!(tuned.get().msg.len(), 100);
+    }
+
+    #[test]
+    fn test_drop_boxed_children() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct Child {
+            drops: Rc<Cell<usize>>,
+        }
+        impl Drop for Child {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+
+        struct Parent<'a> {
+            first: bumpalo::boxed::Box<'a, Child>,
+            rest: bumpalo::collections::Vec<'a, bumpalo::boxed::Box<'a, Child>>,
+        }
+        make_arena_version!(Parent, ArenaParent);
+
+        let drops = Rc::new(Cell::new(0));
+        let mut boxed = ArenaParent::new(|arena| Parent {
+            first: bumpalo::boxed::Box::new_in(
+                Child {
+                    drops: drops.clone(),
+                },
+                arena,
+            ),
+            rest: bumpalo::collections::Vec::new_in(arena),
+        });
+        {
+            let mut handle = boxed.mutate();
+            for _ in 0..2 {
+                let child = handle.alloc_box(Child {
+                    drops: drops.clone(),
+                });
+                handle.rest.push(child);
+            }
+            // Replacing a child drops the old one.
+            handle.first = handle.alloc_box(Child {
+                drops: drops.clone(),
+            });
+            handle.finish();
+        }
+        assert_eq!(drops.get(), 1);
+
+        let boxed = boxed.map_with_scratch(|_, _, mut data| {
+            data.rest.pop();
+            data
+        });
+        assert_eq!(drops.get(), 2);
+
+        drop(boxed);
+        assert_eq!(drops.get(), 4);
+        assert_eq!(Rc::strong_count(&drops), 1);
+    }
+
+    #[test]
+    fn test_new_from_skips_source_destructors() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct Child(Rc<Cell<usize>>);
+        impl Drop for Child {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        struct Parent<'a> {
+            child: bumpalo::boxed::Box<'a, Child>,
+        }
+        make_arena_version!(Parent, ArenaParent);
+
+        let drops = Rc::new(Cell::new(0));
+        let parent = ArenaParent::new(|arena| Parent {
+            child: bumpalo::boxed::Box::new_in(Child(drops.clone()), arena),
+        });
+        let derived = ArenaBox::<&str>::new_from(parent, |arena, parent| {
+            arena.alloc_str(&alloc::format!("{} drops", parent.child.0.get()))
+        });
+        assert_eq!(*derived.get(), "0 drops");
+
+        // The child of the source data is not dropped with the new `ArenaBox`.
+        drop(derived);
+        assert_eq!(drops.get(), 0);
+        assert_eq!(Rc::strong_count(&drops), 2);
+    }
+
+    #[test]
+    fn test_update() {
+        let mut boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("Something"),
+        });
+        assert_eq!(boxed.get().msg, "Something");
+
+        {
+            let mut handle = boxed.mutate();
+            handle.msg = handle.arena().alloc_str("Something different"); // DerefMut
+        }
+
+        let message = boxed.get().msg;
+        assert_eq!(message, "Something different");
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("Something"),
+        });
+        assert_eq!(boxed.mutate().finish(), 0);
+
+        let mut handle = boxed.mutate();
+        handle.msg = handle.arena().alloc_str(&"x".repeat(100));
+        assert!((100..200).contains(&handle.finish()));
+
+        // The count spans the chunks allocated by the mutation.
+        let allocated = boxed.allocated_bytes();
+        let strings = allocated / 100 + 1;
+        let mut handle = boxed.mutate();
+        for _ in 0..strings {
+            handle.msg = handle.arena().alloc_str(&"y".repeat(100));
+        }
+        assert!(handle.finish() >= strings * 100);
+        assert_eq!(boxed.get().msg.len(), 100);
+        assert!(boxed.allocated_bytes() > allocated);
+    }
+
+    #[test]
+    fn test_update_twice() {
+        let mut boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("Something"),
+        });
+        assert_eq!(boxed.get().msg, "Something");
+
+        {
+            let mut handle = boxed.mutate();
+            handle.msg = handle.arena().alloc_str("Something different"); // DerefMut
+        }
+
+        assert_eq!(boxed.get().msg, "Something different");
+
+        {
+            let mut handle = boxed.mutate();
+            handle.msg = handle.arena().alloc_str("Something else"); // DerefMut
+        }
+        assert_eq!(boxed.get().msg, "Something else");
+    }
+
+    struct Buffer<'a> {
+        bytes: &'a mut [u8],
+    }
+    make_arena_version!(Buffer, ArenaBuffer);
+
+    #[test]
+    fn test_reserve() {
+        fn chunk_count<T: WithLifetime>(boxed: &ArenaBox<T>) -> usize {
+            unsafe { boxed.arena.iter_allocated_chunks_raw() }.count()
+        }
+
+        let mut boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        {
+            let mut handle = boxed.mutate();
+            handle.reserve(64 * 100);
+            let arena = handle.arena();
+            let mut msg = "";
+            for _ in 0..64 {
+                msg = arena.alloc_str(&"x".repeat(100));
+            }
+            handle.msg = msg;
+        }
+        assert_eq!(chunk_count(&boxed), 2);
+        assert_eq!(boxed.get().msg.len(), 100);
+
+        // Reserving what is already available does not allocate.
+        let before = boxed.allocated_bytes();
+        boxed.mutate().reserve(0);
+        assert_eq!(boxed.allocated_bytes(), before);
+    }
+
+    #[test]
+    fn test_map_result() {
+        let boxed = ArenaBox::<&[&str]>::new(|arena| {
+            &*arena.alloc_slice_copy(&["keep", "TODO", "keep", "TODO"])
+        });
+        let (boxed, rewritten) = boxed.map_result(|arena, words| {
+            let mut rewritten = 0;
+            let words = arena.alloc_slice_fill_iter(words.iter().map(|&word| {
+                if word == "TODO" {
+                    rewritten += 1;
+                    "DONE"
+                } else {
+                    word
+                }
+            }));
+            (&*words, rewritten)
+        });
+        assert_eq!(*boxed.get(), ["keep", "DONE", "keep", "DONE"]);
+        assert_eq!(rewritten, 2);
+    }
+
+    #[test]
+    fn test_into_std_box() {
+        use alloc::rc::Rc;
+
+        struct Owned(Rc<()>);
+        impl WithLifetime for Owned {
+            type With<'a> = Owned;
+        }
+        unsafe impl ArenaFree for Owned {}
+
+        let rc = Rc::new(());
+        let boxed = ArenaBox::<(Owned, u32)>::new(|_| (Owned(rc.clone()), 7));
+        let owned: Box<(Owned, u32)> = boxed.into_std_box();
+        assert_eq!(owned.1, 7);
+        assert!(Rc::ptr_eq(&owned.0.0, &rc));
+        drop(owned);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_split_fields() {
+        let mut boxed = ArenaAugmentedData::new_from(
+            ArenaData::new(|arena| Data {
+                msg: arena.alloc_str("original"),
+            }),
+            |arena, data| AugmentedData {
+                data,
+                extra: arena.alloc_str("extra"),
+            },
+        );
+        {
+            let mut handle = boxed.mutate();
+            let (data, extra) = split_fields!(handle, data, extra);
+            *extra = data.msg;
+        }
+        assert_eq!(boxed.get().extra, "original");
+
+        let mut data = Data { msg: "a" };
+        let (msg,) = split_fields!(&mut data, msg);
+        *msg = "b";
+        assert_eq!(data.msg, "b");
+    }
+
+    #[test]
+    fn test_format_into() {
+        let mut boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        {
+            let mut handle = boxed.mutate();
+            handle.msg = handle.format_into(format_args!("{:>5}|{:#x}", 42, 255));
+        }
+        assert_eq!(boxed.get().msg, "   42|0xff");
+    }
+
+    #[test]
+    fn test_update_bytes_in_place() {
+        let mut boxed = ArenaBuffer::new(|arena| Buffer {
+            bytes: arena.alloc_slice_copy(&[0x00, 0x0f, 0xf0, 0xff]),
+        });
+        let ptr = boxed.get().bytes.as_ptr();
+        let allocated = boxed.allocated_bytes();
+
+        {
+            let mut handle = boxed.mutate();
+            for byte in handle.bytes.iter_mut() {
+                *byte = !*byte;
+            }
+        }
+
+        assert_eq!(boxed.get().bytes, [0xff, 0xf0, 0x0f, 0x00]);
+        assert_eq!(boxed.get().bytes.as_ptr(), ptr);
+        assert_eq!(boxed.allocated_bytes(), allocated);
+    }
+
+    fn do_something(boxed: ArenaData) {
+        // ArenaBox moved here
+        assert_eq!(boxed.get().msg, "Foo");
+    }
+
+    #[test]
+    fn test_move() {
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("Foo"),
+        });
+        let r = boxed.get();
+        assert_eq!(r.msg, "Foo");
+        do_something(boxed);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct AugmentedData<'arena> {
+        data: &'arena Data<'arena>,
+        extra: &'arena str,
+    }
+
+    make_arena_version!(AugmentedData, ArenaAugmentedData);
+
+    #[test]
+    fn test_new_from() {
+        let a = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+
+        let b = ArenaAugmentedData::new_from(a, |arena, data| AugmentedData {
+            data, // Reference to the original!
+            extra: arena.alloc_str("extra info"),
+        });
+
+        assert_eq!(
+            *b.get(),
+            AugmentedData {
+                data: &Data { msg: "hello" },
+                extra: "extra info",
+            }
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Triple<'arena> {
+        first: &'arena str,
+        second: &'arena Data<'arena>,
+        third: &'arena AugmentedData<'arena>,
+        joined: &'arena str,
+    }
+    make_arena_version!(Triple, ArenaTriple);
+
+    #[test]
+    fn test_new_from3() {
+        let a = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("one"),
+        });
+        let b = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("two"),
+        });
+        let c = ArenaAugmentedData::new_from(
+            ArenaData::new(|arena| Data {
+                msg: arena.alloc_str("three"),
+            }),
+            |arena, data| AugmentedData {
+                data,
+                extra: arena.alloc_str("extra"),
+            },
+        );
+
+        let triple = ArenaTriple::new_from3(a, b, c, |arena, a, b, c| Triple {
+            first: a.msg,
+            second: b,
+            third: c,
+            joined: arena.alloc_str(&alloc::format!("{}-{}-{}", a.msg, b.msg, c.data.msg)),
+        });
+
+        assert_eq!(triple.retained_arenas.len(), 2);
+        assert_eq!(
+            *triple.get(),
+            Triple {
+                first: "one",
+                second: &Data { msg: "two" },
+                third: &AugmentedData {
+                    data: &Data { msg: "three" },
+                    extra: "extra",
+                },
+                joined: "one-two-three",
+            }
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Aggregate<'arena> {
+        parts: &'arena [&'arena Data<'arena>],
+        joined: &'arena str,
+    }
+
+    make_arena_version!(Aggregate, ArenaAggregate);
+
+    #[test]
+    fn test_new_from_slice() {
+        let fragments = ["one", "two", "three"]
+            .into_iter()
+            .map(|msg| {
+                ArenaData::new(|arena| Data {
+                    msg: arena.alloc_str(msg),
+                })
+            })
+            .collect();
+
+        let aggregate = ArenaAggregate::new_from_slice(fragments, |arena, parts| Aggregate {
+            parts,
+            joined: arena.alloc_str(&parts.iter().map(|p| p.msg).collect::<Vec<_>>().join("+")),
+        });
+
+        assert_eq!(aggregate.retained_arenas.len(), 3);
+        let parts = aggregate.get().parts;
+        assert_eq!(parts[0].msg, "one");
+        assert_eq!(parts[1].msg, "two");
+        assert_eq!(parts[2].msg, "three");
+        assert_eq!(aggregate.get().joined, "one+two+three");
+
+        let empty = ArenaAggregate::new_from_slice(Vec::<ArenaData>::new(), |_, parts| Aggregate {
+            parts,
+            joined: "",
+        });
+        assert!(empty.get().parts.is_empty());
+    }
+
+    #[test]
+    fn test_augment() {
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        let old_ptr = boxed.get() as *const Data as usize;
+
+        let mut pass = boxed.augment::<&'static str, _>(|arena, data| {
+            arena.alloc_str(&alloc::format!("{} world", data.msg))
+        });
+        assert_eq!(pass.get().0 as *const Data as usize, old_ptr);
+        assert_eq!(*pass.get(), (&Data { msg: "hello" }, "hello world"));
+
+        {
+            let mut handle = pass.mutate();
+            handle.1 = handle.0.msg;
+        }
+        assert_eq!(pass.get().1, "hello");
+    }
+
+    #[test]
+    fn test_map_both() {
+        let boxed = ArenaAugmentedData::new_from(
+            ArenaData::new(|arena| Data {
+                msg: arena.alloc_str("hello"),
+            }),
+            |arena, data| AugmentedData {
+                data,
+                extra: arena.alloc_str("extra"),
+            },
+        );
+
+        let pass = boxed.map_both::<Data<'static>, _>(|arena, old| Data {
+            msg: arena.alloc_str(&alloc::format!("{} {}", old.data.msg, old.extra)),
+        });
+        let (old, new) = pass.get();
+        assert_eq!(old.data.msg, "hello");
+        assert_eq!(old.extra, "extra");
+        assert_eq!(new.msg, "hello extra");
+    }
+
+    #[test]
+    fn test_grow_into() {
+        use alloc::rc::Rc;
+
+        struct Owned(Rc<()>);
+        impl WithLifetime for Owned {
+            type With<'a> = Owned;
+        }
+
+        let rc = Rc::new(());
+        let boxed =
+            ArenaBox::<(&str, Owned)>::new(|arena| (arena.alloc_str("moved"), Owned(rc.clone())));
+        let text = boxed.get().0.as_ptr();
+
+        let grown = boxed.grow_into::<(Data, (&str, Owned)), _>(|arena, old| {
+            (
+                Data {
+                    msg: arena.alloc_str("new"),
+                },
+                old,
+            )
+        });
+        // The moved fields are not copied, nor dropped.
+        assert!(core::ptr::eq(grown.get().1.0.as_ptr(), text));
+        assert!(Rc::ptr_eq(&grown.get().1.1.0, &rc));
+        assert_eq!(Rc::strong_count(&rc), 2);
+
+        let shrunk = grown.grow_into::<&str, _>(|_, (data, _)| data.msg);
+        assert_eq!(*shrunk.get(), "new");
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_map_with_scratch() {
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        let allocated = boxed.arena.allocated_bytes();
+
+        let boxed = boxed.map_with_scratch(|_arena, scratch, data| {
+            let big = scratch.alloc_slice_fill_copy(64 * 1024, 0u8);
+            assert_eq!(big.len(), 64 * 1024);
+            Data {
+                msg: &data.msg[1..],
+            }
+        });
+
+        assert_eq!(boxed.get().msg, "ello");
+        assert_eq!(boxed.arena.allocated_bytes(), allocated);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+
+        fn shout<'b>(snapshot: Snapshot<'b, Data<'static>>) -> &'b str {
+            snapshot
+                .arena()
+                .alloc_str(&alloc::format!("{}!", snapshot.msg))
+        }
+
+        let snapshot = boxed.snapshot();
+        let copy = snapshot;
+        let shouted = shout(copy);
+        assert_eq!(snapshot.msg, "hello");
+        assert_eq!(shouted, "hello!");
+        assert_eq!(boxed.get().msg, "hello");
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_map_async() {
+        fn block_on<F: Future>(future: F) -> F::Output {
+            let mut future = core::pin::pin!(future);
+            let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+            loop {
+                if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            }
+        }
+
+        struct YieldOnce(bool);
+        impl Future for YieldOnce {
+            type Output = ();
+            fn poll(
+                mut self: Pin<&mut Self>,
+                _cx: &mut core::task::Context<'_>,
+            ) -> core::task::Poll<()> {
+                if core::mem::replace(&mut self.0, true) {
+                    core::task::Poll::Ready(())
+                } else {
+                    core::task::Poll::Pending
+                }
+            }
+        }
+
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("one"),
+        });
+        let boxed = block_on(boxed.map_async(|arena, data| {
+            Box::pin(async move {
+                let first = arena.alloc_str(data.msg);
+                YieldOnce(false).await;
+                Data {
+                    msg: arena.alloc_str(&alloc::format!("{}, two", first)),
+                }
+            })
+        }));
+        assert_eq!(boxed.get().msg, "one, two");
+
+        // Dropping the future halfway through frees the arena without reading the moved value.
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("one"),
+        });
+        let mut future = core::pin::pin!(boxed.map_async(|_, data| {
+            Box::pin(async move {
+                YieldOnce(false).await;
+                data
+            })
+        }));
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+    }
+
+    #[test]
+    fn test_data_type_id() {
+        use core::any::{Any, TypeId};
+
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("Something"),
+        });
+        assert_eq!(boxed.data_type_id(), TypeId::of::<Data<'static>>());
+        // The `Any` implementation of the `ArenaBox` itself reports the type of the box.
+        let any: &dyn Any = &boxed;
+        assert_eq!(any.type_id(), TypeId::of::<ArenaData>());
+        assert_ne!(any.type_id(), boxed.data_type_id());
+        assert!(any.is::<ArenaBox<Data<'static>>>());
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut a = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("first"),
+        });
+        let mut b = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("second"),
+        });
+
+        a.swap(&mut b);
+        assert_eq!(a.get().msg, "second");
+        assert_eq!(b.get().msg, "first");
+
+        {
+            let mut handle = a.mutate();
+            handle.msg = handle.arena().alloc_str("second, updated");
+        }
+        drop(b);
+        assert_eq!(a.get().msg, "second, updated");
+    }
+
+    #[test]
+    fn test_retained_estimate() {
+        let mut boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        let (retained, total) = boxed.retained_estimate();
+        assert_eq!(retained, total);
+        assert!(retained >= "hello".len() + core::mem::size_of::<Data>());
+        assert!(boxed.allocated_bytes() >= total);
+
+        {
+            let mut handle = boxed.mutate();
+            handle.msg = handle.arena().alloc_str("hello world");
+        }
+        let (retained_after, total_after) = boxed.retained_estimate();
+        assert_eq!(retained_after, retained);
+        assert_eq!(total_after, total + "hello world".len());
+
+        let boxed = boxed.map_with_scratch(|_arena, _scratch, data| data);
+        let (retained, total) = boxed.retained_estimate();
+        assert_eq!(retained, total);
+        assert_eq!(total, total_after);
     }
-}
 
-impl<T: core::fmt::Debug + WithLifetime> core::fmt::Debug for ArenaBox<T>
-where
-    for<'a> T::With<'a>: core::fmt::Debug,
-{
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        core::fmt::Debug::fmt(self.get(), f)
+    #[test]
+    fn test_default_slice() {
+        let boxed = ArenaBox::<&[Data<'static>]>::default();
+        assert!(boxed.get().is_empty());
+
+        let smallest = Bump::new();
+        smallest.alloc(0u8);
+        assert_eq!(boxed.allocated_bytes(), smallest.allocated_bytes());
     }
-}
 
-impl<T: WithLifetime> PartialEq for ArenaBox<T>
-where
-    for<'a> T::With<'a>: PartialEq,
-{
-    fn eq(&self, other: &Self) -> bool {
-        self.data == other.data || self.get() == other.get()
+    #[test]
+    fn test_default() {
+        #[derive(Debug, Default, PartialEq)]
+        struct Placeholder<'a> {
+            message: &'a str,
+            parts: &'a [&'a str],
+            code: u32,
+        }
+        make_arena_version!(Placeholder, ArenaPlaceholder);
+
+        let boxed = ArenaPlaceholder::default();
+        assert_eq!(*boxed.get(), Placeholder::default());
+        assert_eq!(boxed.retained_estimate().0, size_of::<Placeholder>());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_new_from_consuming_both() {
+        let kept = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("kept"),
+        });
+        let read = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["a", "b"]));
 
-    #[derive(Debug, PartialEq)]
-    struct Data<'a> {
-        msg: &'a str,
+        let augmented =
+            ArenaAugmentedData::new_from_consuming_both(kept, read, |arena, data, read| {
+                AugmentedData {
+                    data,
+                    extra: arena.alloc_str(&read.concat()),
+                }
+            });
+
+        assert!(augmented.retained_arenas.is_empty());
+        assert_eq!(augmented.get().data.msg, "kept");
+        assert_eq!(augmented.get().extra, "ab");
     }
-    make_arena_version!(Data, ArenaData);
 
+    #[cfg(feature = "debug_validate")]
     #[test]
-    fn test_build() {
+    fn test_poison() {
+        let mut arena = Bump::new();
+        arena.alloc_str("some data");
+        arena.alloc_slice_copy(&[1u64, 2, 3]);
+        poison(&mut arena);
+        for chunk in arena.iter_allocated_chunks() {
+            assert!(
+                chunk
+                    .iter()
+                    .all(|byte| unsafe { byte.assume_init() } == POISON_BYTE)
+            );
+        }
+
+        // Dropping moved and combined boxes must not double free their arenas.
         let boxed = ArenaData::new(|arena| Data {
-            msg: arena.alloc_str("Something"),
+            msg: arena.alloc_str("hello"),
         });
-        assert_eq!(boxed.get().msg, "Something");
+        let other = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("world"),
+        });
+        drop(ArenaBox::<Data<'static>>::new_from2(
+            boxed,
+            other,
+            |_, first, _| Data { msg: first.msg },
+        ));
     }
 
     #[test]
-    fn test_update() {
+    fn test_debug_chunks() {
         let mut boxed = ArenaData::new(|arena| Data {
-            msg: arena.alloc_str("Something"),
+            msg: arena.alloc_str("small"),
         });
-        assert_eq!(boxed.get().msg, "Something");
+        assert_eq!(boxed.debug_chunks().count(), 1);
+        let (start, len) = boxed.debug_chunks().next().unwrap();
+        let msg = boxed.get().msg.as_ptr() as usize;
+        assert!((start as usize..start as usize + len).contains(&msg));
+
+        let mut counts = Vec::new();
+        // Each allocation is larger than the room left in the newest chunk.
+        for i in 0..4 {
+            let mut handle = boxed.mutate();
+            handle.msg = handle
+                .arena()
+                .alloc_str(&"x".repeat((16 * 1024) << (2 * i)));
+            handle.finish();
+            counts.push(boxed.debug_chunks().count());
+        }
+        assert!(counts.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            boxed.debug_chunks().count(),
+            boxed.arena_stats().chunk_count
+        );
+    }
 
+    #[test]
+    fn test_arena_stats() {
+        let first = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("first"),
+        });
+        let second = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("second"),
+        });
+        let mut boxed = ArenaBox::<Data<'static>>::new_from2(first, second, |_, first, _| Data {
+            msg: first.msg,
+        });
         {
             let mut handle = boxed.mutate();
-            handle.msg = handle.arena().alloc_str("Something different"); // DerefMut
+            handle.msg = handle.arena().alloc_str(&"x".repeat(4096));
         }
 
-        let message = boxed.get().msg;
-        assert_eq!(message, "Something different");
+        let stats = boxed.arena_stats();
+        assert_eq!(stats.allocated_bytes, boxed.allocated_bytes());
+        assert_eq!(
+            (stats.built_bytes, stats.used_bytes),
+            boxed.retained_estimate()
+        );
+        assert_eq!(stats.chunk_count, 2);
+        assert_eq!(stats.retained_arenas, 1);
     }
 
     #[test]
-    fn test_update_twice() {
-        let mut boxed = ArenaData::new(|arena| Data {
-            msg: arena.alloc_str("Something"),
+    fn test_retarget() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct Counted<'a> {
+            msg: &'a str,
+            drops: Rc<Cell<usize>>,
+        }
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.drops.set(self.drops.get() + 1);
+            }
+        }
+        make_arena_version!(Counted, ArenaCounted);
+
+        let drops = Rc::new(Cell::new(0));
+        let pair = ArenaBox::<(Data, bumpalo::boxed::Box<Counted>)>::new(|arena| {
+            (
+                Data {
+                    msg: arena.alloc_str("first"),
+                },
+                bumpalo::boxed::Box::new_in(
+                    Counted {
+                        msg: arena.alloc_str("second"),
+                        drops: drops.clone(),
+                    },
+                    arena,
+                ),
+            )
         });
-        assert_eq!(boxed.get().msg, "Something");
+        let mut counted: ArenaCounted = pair.retarget(|(_, counted)| &mut **counted);
+        assert_eq!(counted.get().msg, "second");
 
         {
-            let mut handle = boxed.mutate();
-            handle.msg = handle.arena().alloc_str("Something different"); // DerefMut
+            let mut handle = counted.mutate();
+            handle.msg = handle.arena().alloc_str("changed");
+        }
+        assert_eq!(counted.get().msg, "changed");
+        assert_eq!(drops.get(), 0);
+        drop(counted);
+        assert_eq!(drops.get(), 1);
+    }
+
+    #[test]
+    fn test_retarget_skips_sibling_destructors() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct Sibling(Rc<Cell<usize>>);
+        impl Drop for Sibling {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
         }
 
-        assert_eq!(boxed.get().msg, "Something different");
+        struct Record<'a> {
+            data: Data<'a>,
+            sibling: bumpalo::boxed::Box<'a, Sibling>,
+        }
+        make_arena_version!(Record, ArenaRecord);
+
+        let drops = Rc::new(Cell::new(0));
+        let record = ArenaRecord::new(|arena| Record {
+            data: Data {
+                msg: arena.alloc_str("kept"),
+            },
+            sibling: bumpalo::boxed::Box::new_in(Sibling(drops.clone()), arena),
+        });
+        assert_eq!(Rc::strong_count(&record.get().sibling.0), 2);
+        let data: ArenaData = record.retarget(|record| &mut record.data);
+        assert_eq!(data.get().msg, "kept");
+
+        // The sibling field is not dropped, neither when narrowing nor with the new `ArenaBox`.
+        assert_eq!(drops.get(), 0);
+        drop(data);
+        assert_eq!(drops.get(), 0);
+        assert_eq!(Rc::strong_count(&drops), 2);
+    }
+
+    #[test]
+    fn test_assert_arena_within() {
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        boxed.assert_arena_within(boxed.allocated_bytes());
+    }
+
+    #[test]
+    #[should_panic(expected = "more than the limit of 16 bytes")]
+    fn test_assert_arena_within_exceeded() {
+        let boxed = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        boxed.assert_arena_within(16);
+    }
+
+    #[test]
+    fn test_collect_vec() {
+        let mut boxed = ArenaBox::<bumpalo::collections::Vec<'static, usize>>::collect_vec(0..4);
+        assert_eq!(**boxed.get(), [0, 1, 2, 3]);
 
         {
             let mut handle = boxed.mutate();
-            handle.msg = handle.arena().alloc_str("Something else"); // DerefMut
+            handle.extend(4..100);
+            handle.retain(|i| i % 10 == 0);
         }
-        assert_eq!(boxed.get().msg, "Something else");
+        assert_eq!(**boxed.get(), [0, 10, 20, 30, 40, 50, 60, 70, 80, 90]);
     }
 
-    fn do_something(boxed: ArenaData) {
-        // ArenaBox moved here
-        assert_eq!(boxed.get().msg, "Foo");
+    #[test]
+    fn test_arena_vec_box() {
+        let mut lengths = ArenaVecBox::<(u8, usize)>::new_vec();
+        assert!(lengths.is_empty());
+        for word in "the quick brown fox".split(' ') {
+            lengths.push((word.as_bytes()[0], word.len()));
+        }
+        assert_eq!(lengths.len(), 4);
+        assert_eq!(lengths.as_slice()[1..3], [(b'q', 5), (b'b', 5)]);
+        assert_eq!(lengths.iter().map(|(_, len)| len).sum::<usize>(), 16);
+
+        let mut numbers = ArenaVecBox::<u64>::collect_vec(0..3);
+        for i in 3..1000 {
+            numbers.push(i);
+        }
+        assert_eq!(numbers.len(), 1000);
+        assert!(numbers.iter().copied().eq(0..1000));
     }
 
     #[test]
-    fn test_move() {
-        let boxed = ArenaData::new(|arena| Data {
-            msg: arena.alloc_str("Foo"),
+    fn test_retain() {
+        let mut numbers = ArenaVecBox::<u32>::collect_vec(0..10);
+        numbers.retain(|n| n % 2 == 0);
+        assert_eq!(numbers.len(), 5);
+        assert_eq!(numbers.as_slice(), [0, 2, 4, 6, 8]);
+
+        // Elements can still be pushed after pruning.
+        numbers.push(10);
+        numbers.retain(|n| *n > 4);
+        assert_eq!(numbers.as_slice(), [6, 8, 10]);
+
+        numbers.retain(|_| false);
+        assert!(numbers.is_empty());
+    }
+
+    #[test]
+    fn test_append() {
+        let mut boxed = ArenaBox::<bumpalo::collections::Vec<'static, u64>>::collect_vec([]);
+        for i in 0..500 {
+            if i % 2 == 0 {
+                boxed.append(i);
+            } else {
+                boxed += i;
+            }
+        }
+        assert_eq!(boxed.get().len(), 500);
+        assert_eq!(boxed.get().iter().sum::<u64>(), 499 * 500 / 2);
+    }
+
+    #[test]
+    fn test_map_elements() {
+        let numbers = ArenaBox::<&[i64]>::new(|arena| &*arena.alloc_slice_copy(&[7, -42, 0]));
+        let strings = numbers
+            .map_elements::<&'static str, _>(|arena, n| arena.alloc_str(&alloc::format!("{n}")));
+        assert_eq!(*strings.get(), ["7", "-42", "0"]);
+
+        let lengths = strings.map_elements::<usize, _>(|_, s| s.len());
+        assert_eq!(*lengths.get(), [1, 3, 1]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_map_par() {
+        let numbers = ArenaBox::<&[u32]>::new(|arena| &*arena.alloc_slice_fill_iter(0..10_000));
+        let squares = numbers.map_par::<&'static str, _, _, _>(
+            |&n| alloc::format!("{}", u64::from(n) * u64::from(n)),
+            |arena, square| arena.alloc_str(&square),
+        );
+        assert_eq!(squares.get().len(), 10_000);
+        assert_eq!(squares.get()[3], "9");
+        assert_eq!(squares.get()[9_999], "99980001");
+    }
+
+    #[test]
+    fn test_as_bytes() {
+        let empty = ArenaBox::<&[u8]>::default();
+        assert!(empty.is_empty());
+        assert_eq!(empty.as_bytes(), b"");
+
+        let header = ArenaBox::<&[u8]>::new(|arena| arena.alloc_slice_fill_iter(0..4));
+        assert_eq!(header.len(), 4);
+        assert_eq!(header.as_bytes(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let exact = ArenaBox::<&[u64]>::from_iter_exact((0..1000u32).map(u64::from));
+        assert_eq!(exact.len(), 1000);
+        assert_eq!(exact.get()[999], 999);
+        // The slice is allocated once, with no room left for a growing copy.
+        assert!(exact.allocated_bytes() < 2 * 1000 * core::mem::size_of::<u64>());
+
+        let lazy: ArenaBox<&[u64]> = (0..).take_while(|i| i * i < 50).collect();
+        assert_eq!(*lazy.get(), [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        let empty: ArenaBox<&[u64]> = core::iter::empty().collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_to_vec() {
+        let boxed = ArenaBox::<&[i32]>::new(|arena| &*arena.alloc_slice_fill_iter(-2..3));
+        let numbers = boxed.to_vec();
+        drop(boxed);
+        assert_eq!(numbers, [-2, -1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_map_indexed() {
+        let words = ArenaBox::<&[&str]>::new(|arena| &*arena.alloc_slice_copy(&["x", "y", "z"]));
+        let tagged = words.map_indexed::<&'static str, _>(|i, arena, word| {
+            arena.alloc_str(&alloc::format!("{i}:{word}"))
         });
-        let r = boxed.get();
-        assert_eq!(r.msg, "Foo");
-        do_something(boxed);
+        assert_eq!(*tagged.get(), ["0:x", "1:y", "2:z"]);
     }
 
-    #[derive(Debug, PartialEq)]
-    struct AugmentedData<'arena> {
-        data: &'arena Data<'arena>,
-        extra: &'arena str,
+    #[test]
+    fn test_map_dedup() {
+        let nodes = ArenaBox::<&[&str]>::new(|arena| {
+            let mut nodes = ["b", "a", "c", "a", "b", "a"].map(|s| &*arena.alloc_str(s));
+            nodes.sort();
+            &*arena.alloc_slice_copy(&nodes)
+        });
+        let nodes = nodes.map_dedup();
+        assert_eq!(*nodes.get(), ["a", "b", "c"]);
+
+        let runs = ArenaBox::<&[u8]>::new(|arena| arena.alloc_slice_copy(b"aaabccddda"));
+        assert_eq!(runs.map_dedup().as_bytes(), b"abcda");
+
+        assert!(ArenaBox::<&[u8]>::default().map_dedup().is_empty());
     }
 
-    make_arena_version!(AugmentedData, ArenaAugmentedData);
+    #[test]
+    fn test_map_group_by() {
+        let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[2, 4, 1, 3, 5, 6]));
+        let groups = numbers.map_group_by::<bool, _>(|n| n % 2 == 0);
+        assert_eq!(
+            *groups.get(),
+            [
+                (true, &[2, 4][..]),
+                (false, &[1, 3, 5][..]),
+                (true, &[6][..])
+            ]
+        );
+
+        let empty = ArenaBox::<&[u32]>::default().map_group_by::<bool, _>(|n| n % 2 == 0);
+        assert!(empty.is_empty());
+    }
 
     #[test]
-    fn test_new_from() {
-        let a = ArenaData::new(|arena| Data {
-            msg: arena.alloc_str("hello"),
+    fn test_map_partition() {
+        let numbers = ArenaBox::<&[i32]>::new(|arena| arena.alloc_slice_fill_iter(-3..5));
+        let parts = numbers.map_partition(|n| n % 2 == 0);
+        let (evens, odds) = *parts.get();
+        assert_eq!(evens, [-2, 0, 2, 4]);
+        assert_eq!(odds, [-3, -1, 1, 3]);
+
+        let nodes = ArenaBox::<&[&str]>::new(|arena| arena.alloc_slice_copy(&["fn", "let"]));
+        let parts = nodes.map_partition(|_| true);
+        assert_eq!(*parts.get(), (&["fn", "let"][..], &[][..]));
+    }
+
+    #[test]
+    fn test_map_flatten() {
+        let nested = ArenaBox::<&[&[u32]]>::new(|arena| {
+            arena.alloc_slice_copy(&[
+                &*arena.alloc_slice_copy(&[1, 2]),
+                &*arena.alloc_slice_copy(&[3]),
+                &[],
+                &*arena.alloc_slice_copy(&[4, 5]),
+            ])
         });
+        let flat = nested.map_flatten();
+        assert_eq!(*flat.get(), [1, 2, 3, 4, 5]);
 
-        let b = ArenaAugmentedData::new_from(a, |arena, data| AugmentedData {
-            data, // Reference to the original!
-            extra: arena.alloc_str("extra info"),
+        assert!(ArenaBox::<&[&[u32]]>::default().map_flatten().is_empty());
+    }
+
+    #[test]
+    fn test_map_rev() {
+        let words = ArenaBox::<&[&str]>::new(|arena| {
+            arena.alloc_slice_fill_iter(["one", "two", "three"].map(|s| &*arena.alloc_str(s)))
+        });
+        let reversed = words.map_rev::<&str, _>(|_, word| *word);
+        assert_eq!(*reversed.get(), ["three", "two", "one"]);
+
+        let upper =
+            reversed.map_rev::<&str, _>(|arena, word| arena.alloc_str(&word.to_uppercase()));
+        assert_eq!(*upper.get(), ["ONE", "TWO", "THREE"]);
+    }
+
+    #[test]
+    fn test_map_windows() {
+        let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 2, 3, 4, 5]));
+        let sums = numbers.map_windows::<2, u32, _>(|_, [a, b]| a + b);
+        assert_eq!(*sums.get(), [3, 5, 7, 9]);
+
+        let pairs = sums
+            .map_windows::<2, &str, _>(|arena, [a, b]| arena.alloc_str(&alloc::format!("{a}+{b}")));
+        assert_eq!(*pairs.get(), ["3+5", "5+7", "7+9"]);
+
+        let short = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[1, 2]));
+        assert!(short.map_windows::<3, u32, _>(|_, _| 0).is_empty());
+    }
+
+    #[test]
+    fn test_map_with_index_and_prev() {
+        let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_copy(&[3, 1, 4, 1, 5]));
+        let sums = numbers.map_with_index_and_prev::<(usize, u32), _>(|i, _, prev, n| {
+            (i, prev.map_or(0, |(_, sum)| *sum) + n)
+        });
+        assert_eq!(*sums.get(), [(0, 3), (1, 4), (2, 8), (3, 9), (4, 14)]);
+
+        let empty = ArenaBox::<&[u32]>::default();
+        let sums = empty.map_with_index_and_prev::<u32, _>(|_, _, _, n| *n);
+        assert!(sums.is_empty());
+    }
+
+    #[test]
+    fn test_map_filter() {
+        let numbers = ArenaBox::<&[u32]>::new(|arena| &*arena.alloc_slice_fill_iter(1..8));
+        let even = numbers.map_filter::<&'static str, _>(|arena, &n| {
+            (n % 2 == 0).then(|| &*arena.alloc_str(&alloc::format!("{n}")))
         });
+        assert_eq!(*even.get(), ["2", "4", "6"]);
+    }
 
+    #[test]
+    fn test_map_chunked() {
+        let numbers = ArenaBox::<&[u32]>::new(|arena| arena.alloc_slice_fill_iter(0..10));
+        let mut sizes = Vec::new();
+        let chunks = numbers.map_chunked::<(u32, u32), _>(4, |_, chunk, out| {
+            sizes.push(chunk.len());
+            out.extend(chunk.iter().map(|n| (chunk[0], *n)));
+        });
+        assert_eq!(sizes, [4, 4, 2]);
         assert_eq!(
-            *b.get(),
-            AugmentedData {
-                data: &Data { msg: "hello" },
-                extra: "extra info",
-            }
+            *chunks.get(),
+            [
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (0, 3),
+                (4, 4),
+                (4, 5),
+                (4, 6),
+                (4, 7),
+                (8, 8),
+                (8, 9)
+            ]
         );
     }
 
+    #[test]
+    fn test_map_while() {
+        let numbers = ArenaBox::<&[i32]>::new(|arena| &*arena.alloc_slice_copy(&[4, 9, -1, 16]));
+        let mut calls = 0;
+        let roots = numbers.map_while::<&'static str, _>(|arena, &n| {
+            calls += 1;
+            (n >= 0).then(|| &*arena.alloc_str(&alloc::format!("{}", n.isqrt())))
+        });
+        assert_eq!(*roots.get(), ["2", "3"]);
+        assert_eq!(calls, 3);
+
+        let none = roots.map_while::<u8, _>(|_, _| None);
+        assert!(none.get().is_empty());
+    }
+
+    #[test]
+    fn test_map_fallible_elements() {
+        let numbers = ArenaBox::<&[i32]>::new(|arena| &*arena.alloc_slice_copy(&[1, 2, -3, 4]));
+        let mut calls = 0;
+        let Err((numbers, err)) = numbers.map_fallible_elements::<u32, _, _>(|_, n| {
+            calls += 1;
+            u32::try_from(*n).map_err(|_| *n)
+        }) else {
+            panic!("Expected an error");
+        };
+        assert_eq!(err, -3);
+        assert_eq!(calls, 3);
+        assert_eq!(*numbers.get(), [1, 2, -3, 4]);
+
+        let numbers = numbers.map_elements::<i32, _>(|_, n| n.abs());
+        let Ok(unsigned) =
+            numbers.map_fallible_elements::<u32, _, _>(|_, n| u32::try_from(*n).map_err(|_| *n))
+        else {
+            panic!("Expected success");
+        };
+        assert_eq!(*unsigned.get(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_try_map_elements() {
+        let numbers = ArenaBox::<&[i32]>::new(|arena| arena.alloc_slice_copy(&[4, 1, -2, 7, -3]));
+
+        let Err((numbers, index, err)) =
+            numbers.try_map_elements::<u32, _, _>(|_, n| u32::try_from(*n).map_err(|_| "negative"))
+        else {
+            panic!("Expected an error");
+        };
+        assert_eq!((index, err), (2, "negative"));
+        assert_eq!(numbers.get()[index], -2);
+
+        let doubled = numbers
+            .try_map_elements::<i32, (), _>(|_, n| Ok(n * 2))
+            .unwrap();
+        assert_eq!(*doubled.get(), [8, 2, -4, 14, -6]);
+    }
+
+    #[test]
+    fn test_insert_sorted() {
+        let mut boxed = ArenaBox::<&[i32]>::new(|arena| &*arena.alloc_slice_copy(&[]));
+        for item in [5, -1, 3, 3, 10, 0] {
+            boxed = boxed.insert_sorted(item);
+        }
+        assert_eq!(*boxed.get(), [-1, 0, 3, 3, 5, 10]);
+    }
+
     #[derive(Debug, PartialEq)]
     struct MyError<'arena> {
         message: &'arena str,
@@ -438,6 +5806,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_map_err_arena() {
+        fn check(boxed: ArenaData) -> Result<(ArenaData, usize), ArenaMyError> {
+            let result = if boxed.get().msg.is_empty() {
+                Err("empty message")
+            } else {
+                Ok(boxed.get().msg.len())
+            };
+            boxed.map_err_arena(result, |arena, data, err| MyError {
+                message: err,
+                details: arena.alloc_str(&alloc::format!("while checking {:?}", data.msg)),
+            })
+        }
+
+        let ok = ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("hello"),
+        });
+        let Ok((ok, len)) = check(ok) else {
+            panic!("Expected success");
+        };
+        assert_eq!(ok.get().msg, "hello");
+        assert_eq!(len, 5);
+
+        let empty = ArenaData::new(|_| Data { msg: "" });
+        let Err(e) = check(empty) else {
+            panic!("Expected an error");
+        };
+        assert_eq!(
+            *e.get(),
+            MyError {
+                message: "empty message",
+                details: "while checking \"\"",
+            }
+        );
+    }
+
+    impl ArenaEqBy for MyError<'static> {
+        type Key<'a> = &'a str;
+
+        fn key<'a>(value: &'a MyError<'a>) -> &'a str {
+            value.message
+        }
+    }
+
+    #[test]
+    fn test_eq_by() {
+        let a = ArenaMyError::new(|arena| MyError {
+            message: arena.alloc_str("an error happened"),
+            details: arena.alloc_str("at 10:00"),
+        });
+        let b = ArenaMyError::new(|arena| MyError {
+            message: arena.alloc_str("an error happened"),
+            details: arena.alloc_str("at 11:00"),
+        });
+        let c = ArenaMyError::new(|arena| MyError {
+            message: arena.alloc_str("another error happened"),
+            details: arena.alloc_str("at 10:00"),
+        });
+        assert_ne!(a, b);
+        assert!(a.eq_by(&b));
+        assert!(!a.eq_by(&c));
+    }
+
     #[test]
     fn test_equality() {
         let a = ArenaMyError::new(|arena| MyError {
@@ -455,4 +5886,14 @@ mod tests {
         }
         assert_ne!(a, b);
     }
+
+    #[test]
+    fn test_structural_eq() {
+        let a = ArenaBox::<(&str, f32)>::new(|arena| (arena.alloc_str("nan"), f32::NAN));
+        let b = ArenaBox::<(&str, f32)>::new(|arena| (arena.alloc_str("one"), 1.0));
+        assert!(a == a);
+        assert!(!a.structural_eq(&a));
+        assert!(b.structural_eq(&b));
+        assert!(!a.structural_eq(&b));
+    }
 }
@@ -0,0 +1,254 @@
+use alloc::collections::TryReserveError;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::WithLifetime;
+
+/// A trait for deep copying arena allocated data into an owned value.
+///
+/// It is implemented by this crate for the types it implements [`WithLifetime`] for, and can be
+/// implemented for your own types to use [`ArenaBox::into_owned`](crate::ArenaBox::into_owned)
+/// and [`ArenaBox::try_into_owned`](crate::ArenaBox::try_into_owned).
+/// With the `derive` feature, `#[derive(IntoOwned)]` implements it for a struct along with its
+/// owned mirror type.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+/// use std::collections::TryReserveError;
+///
+/// pub struct Data<'a> {
+///     msg: &'a str,
+/// }
+///
+/// make_arena_version!(Data, pub ArenaData);
+///
+/// #[derive(Debug, PartialEq)]
+/// pub struct OwnedData {
+///     msg: String,
+/// }
+///
+/// impl IntoOwned for Data<'static> {
+///     type Owned = OwnedData;
+///
+///     fn into_owned(value: &Data<'_>) -> OwnedData {
+///         OwnedData {
+///             msg: <&str>::into_owned(&value.msg),
+///         }
+///     }
+///
+///     fn try_into_owned(value: &Data<'_>) -> Result<OwnedData, TryReserveError> {
+///         Ok(OwnedData {
+///             msg: <&str>::try_into_owned(&value.msg)?,
+///         })
+///     }
+/// }
+///
+/// let boxed = ArenaData::new(|arena| Data {
+///     msg: arena.alloc_str("Something"),
+/// });
+///
+/// assert_eq!(boxed.into_owned(), OwnedData { msg: "Something".into() });
+/// ```
+pub trait IntoOwned: WithLifetime {
+    /// The owned counterpart of the arena allocated type.
+    type Owned;
+
+    /// Deep copies `value` out of the arena.
+    fn into_owned(value: &Self::With<'_>) -> Self::Owned;
+
+    /// Deep copies `value` out of the arena, returning an error instead of aborting if
+    /// allocating the owned value fails.
+    ///
+    /// The default implementation calls [`IntoOwned::into_owned`], so it is only fallible for
+    /// types overriding it.
+    fn try_into_owned(value: &Self::With<'_>) -> Result<Self::Owned, TryReserveError> {
+        Ok(Self::into_owned(value))
+    }
+
+    /// Returns `value` as a reference to the owned type, if the arena allocated type is the
+    /// owned type itself.
+    ///
+    /// This is used by [`ArenaBox::get_cow`](crate::ArenaBox::get_cow) to avoid copying. The
+    /// default implementation returns `None`; the implementations for primitive types return
+    /// `Some`.
+    fn as_owned<'b>(value: &'b Self::With<'_>) -> Option<&'b Self::Owned> {
+        let _ = value;
+        None
+    }
+}
+
+impl IntoOwned for &'static str {
+    type Owned = String;
+
+    fn into_owned(value: &&str) -> String {
+        String::from(*value)
+    }
+
+    fn try_into_owned(value: &&str) -> Result<String, TryReserveError> {
+        let mut owned = String::new();
+        owned.try_reserve_exact(value.len())?;
+        owned.push_str(value);
+        Ok(owned)
+    }
+}
+
+impl<E: IntoOwned> IntoOwned for &'static [E] {
+    type Owned = Vec<E::Owned>;
+
+    fn into_owned(value: &&[E::With<'_>]) -> Vec<E::Owned> {
+        value.iter().map(E::into_owned).collect()
+    }
+
+    fn try_into_owned(value: &&[E::With<'_>]) -> Result<Vec<E::Owned>, TryReserveError> {
+        try_collect::<E>(value)
+    }
+}
+
+impl<T: IntoOwned> IntoOwned for &'static T {
+    type Owned = T::Owned;
+
+    fn into_owned(value: &&T::With<'_>) -> T::Owned {
+        T::into_owned(value)
+    }
+
+    fn try_into_owned(value: &&T::With<'_>) -> Result<T::Owned, TryReserveError> {
+        T::try_into_owned(value)
+    }
+
+    fn as_owned<'b>(value: &'b &T::With<'_>) -> Option<&'b T::Owned> {
+        T::as_owned(*value)
+    }
+}
+
+impl<A: IntoOwned, B: IntoOwned> IntoOwned for (A, B) {
+    type Owned = (A::Owned, B::Owned);
+
+    fn into_owned((a, b): &(A::With<'_>, B::With<'_>)) -> Self::Owned {
+        (A::into_owned(a), B::into_owned(b))
+    }
+
+    fn try_into_owned((a, b): &(A::With<'_>, B::With<'_>)) -> Result<Self::Owned, TryReserveError> {
+        Ok((A::try_into_owned(a)?, B::try_into_owned(b)?))
+    }
+}
+
+impl<E: IntoOwned> IntoOwned for bumpalo::collections::Vec<'static, E> {
+    type Owned = Vec<E::Owned>;
+
+    fn into_owned(value: &bumpalo::collections::Vec<'_, E::With<'_>>) -> Vec<E::Owned> {
+        value.iter().map(E::into_owned).collect()
+    }
+
+    fn try_into_owned(
+        value: &bumpalo::collections::Vec<'_, E::With<'_>>,
+    ) -> Result<Vec<E::Owned>, TryReserveError> {
+        try_collect::<E>(value)
+    }
+}
+
+fn try_collect<E: IntoOwned>(values: &[E::With<'_>]) -> Result<Vec<E::Owned>, TryReserveError> {
+    let mut owned = Vec::new();
+    owned.try_reserve_exact(values.len())?;
+    for value in values {
+        owned.push(E::try_into_owned(value)?);
+    }
+    Ok(owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArenaBox;
+
+    #[test]
+    fn test_into_owned() {
+        let boxed = ArenaBox::<&[(&str, &u32)]>::new(|arena| {
+            &*arena.alloc_slice_fill_iter([
+                (&*arena.alloc_str("one"), &*arena.alloc(1)),
+                (&*arena.alloc_str("two"), &*arena.alloc(2)),
+            ])
+        });
+        let expected = [(String::from("one"), 1), (String::from("two"), 2)];
+
+        assert_eq!(boxed.into_owned(), expected);
+        assert_eq!(boxed.try_into_owned().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_validate_against() {
+        let boxed = ArenaBox::<(&str, &[u32])>::new(|arena| {
+            (arena.alloc_str("ids"), &*arena.alloc_slice_copy(&[1, 2]))
+        });
+
+        assert!(boxed.validate_against(&(String::from("ids"), alloc::vec![1, 2])));
+        assert!(!boxed.validate_against(&(String::from("ids"), alloc::vec![1, 3])));
+        assert!(!boxed.validate_against(&(String::from("id"), alloc::vec![1, 2])));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive() {
+        use crate::IntoOwned;
+        use alloc::boxed::Box;
+        use bumpalo::collections::Vec as BumpVec;
+
+        fn count(values: &BumpVec<'_, u32>) -> usize {
+            values.len()
+        }
+
+        #[derive(IntoOwned)]
+        #[into_owned(derive(Debug, PartialEq))]
+        struct Record<'a> {
+            name: &'a str,
+            tags: &'a [&'a str],
+            score: &'a u32,
+            #[into_owned(clone)]
+            id: String,
+            #[into_owned(owned = usize, with = count)]
+            values: BumpVec<'a, u32>,
+        }
+        crate::make_arena_version!(Record, ArenaRecord);
+
+        #[derive(IntoOwned)]
+        #[into_owned(name = OwnedPair, derive(Debug, PartialEq))]
+        struct Pair<'a>(&'a str, u8);
+        crate::make_arena_version!(Pair, ArenaPair);
+
+        let record = ArenaRecord::new(|arena| Record {
+            name: arena.alloc_str("first"),
+            tags: arena.alloc_slice_copy(&["a", "b"]),
+            score: arena.alloc(10),
+            id: String::from("1"),
+            values: BumpVec::from_iter_in([1, 2, 3], arena),
+        });
+        let expected = RecordOwned {
+            name: String::from("first"),
+            tags: alloc::vec![String::from("a"), String::from("b")],
+            score: Box::new(10),
+            id: String::from("1"),
+            values: 3,
+        };
+        assert_eq!(record.into_owned(), expected);
+        assert_eq!(record.try_into_owned().unwrap(), expected);
+
+        let pair = ArenaPair::new(|arena| Pair(arena.alloc_str("two"), 2));
+        assert_eq!(pair.into_owned(), OwnedPair(String::from("two"), 2));
+    }
+
+    #[test]
+    fn test_get_cow() {
+        use alloc::borrow::Cow;
+
+        let number = ArenaBox::<&&u8>::new(|arena| arena.alloc(&*arena.alloc(7)));
+        assert!(matches!(number.get_cow(), Cow::Borrowed(&7)));
+
+        let pair = ArenaBox::<(&str, u8)>::new(|arena| (arena.alloc_str("seven"), 7));
+        assert_eq!(
+            pair.get_cow(),
+            Cow::<(String, u8)>::Owned((String::from("seven"), 7))
+        );
+        assert!(matches!(pair.get_cow(), Cow::Owned(_)));
+    }
+}
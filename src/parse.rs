@@ -0,0 +1,130 @@
+use bumpalo::Bump;
+
+use crate::{ArenaBox, WithLifetime};
+
+/// A trait for parsing arena allocated data from a string.
+///
+/// Implementing it gives `ArenaBox<T>` a `TryFrom<&str>` implementation. The parser copies the
+/// substrings it keeps into the arena, so the resulting `ArenaBox` does not borrow from the
+/// input.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+/// use bumpalo::Bump;
+///
+/// pub struct Entry<'a> {
+///     key: &'a str,
+///     value: &'a str,
+/// }
+///
+/// make_arena_version!(Entry, pub ArenaEntry);
+///
+/// impl ParseInArena for Entry<'static> {
+///     type Err = &'static str;
+///
+///     fn parse_in<'a>(arena: &'a Bump, s: &str) -> Result<Entry<'a>, &'static str> {
+///         let (key, value) = s.split_once('=').ok_or("missing '='")?;
+///         Ok(Entry {
+///             key: arena.alloc_str(key.trim()),
+///             value: arena.alloc_str(value.trim()),
+///         })
+///     }
+/// }
+///
+/// let entry: ArenaEntry = "name = arena".try_into().unwrap();
+/// assert_eq!((entry.get().key, entry.get().value), ("name", "arena"));
+///
+/// assert!(ArenaEntry::try_from("name").is_err());
+/// ```
+pub trait ParseInArena: WithLifetime {
+    /// The error returned when parsing fails.
+    type Err;
+
+    /// Parses `s`, allocating the parsed data in `arena`.
+    fn parse_in<'a>(arena: &'a Bump, s: &str) -> Result<Self::With<'a>, Self::Err>;
+}
+
+impl ParseInArena for &'static str {
+    type Err = core::convert::Infallible;
+
+    fn parse_in<'a>(arena: &'a Bump, s: &str) -> Result<&'a str, Self::Err> {
+        Ok(arena.alloc_str(s))
+    }
+}
+
+impl<T: ParseInArena> TryFrom<&str> for ArenaBox<T> {
+    type Error = T::Err;
+
+    fn try_from(s: &str) -> Result<Self, T::Err> {
+        Self::try_build_in(Bump::new(), |arena| T::parse_in(arena, s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    struct Config<'a> {
+        name: &'a str,
+        values: &'a [(&'a str, u32)],
+    }
+
+    crate::make_arena_version!(Config, ArenaConfig);
+
+    #[derive(Debug, PartialEq)]
+    enum ConfigError {
+        MissingName,
+        BadLine(usize),
+    }
+
+    // Parses a `[name]` header followed by `key = number` lines.
+    impl ParseInArena for Config<'static> {
+        type Err = ConfigError;
+
+        fn parse_in<'a>(arena: &'a Bump, s: &str) -> Result<Config<'a>, ConfigError> {
+            let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+            let name = lines
+                .next()
+                .and_then(|line| line.strip_prefix('[')?.strip_suffix(']'))
+                .ok_or(ConfigError::MissingName)?;
+            let mut values = bumpalo::collections::Vec::new_in(arena);
+            for (i, line) in lines.enumerate() {
+                let (key, value) = line
+                    .split_once('=')
+                    .and_then(|(key, value)| Some((key.trim(), value.trim().parse().ok()?)))
+                    .ok_or(ConfigError::BadLine(i + 1))?;
+                values.push((&*arena.alloc_str(key), value));
+            }
+            Ok(Config {
+                name: arena.alloc_str(name),
+                values: values.into_bump_slice(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        let text = String::from("[server]\nport = 8080\n\nworkers = 4\n");
+        let config: ArenaConfig = text.as_str().try_into().unwrap();
+        // The box doesn't borrow from the input.
+        drop(text);
+
+        assert_eq!(config.get().name, "server");
+        assert_eq!(config.get().values, [("port", 8080), ("workers", 4)]);
+
+        assert_eq!(
+            ArenaConfig::try_from("port = 8080").err(),
+            Some(ConfigError::MissingName)
+        );
+        assert_eq!(
+            ArenaConfig::try_from("[server]\nport = x").err(),
+            Some(ConfigError::BadLine(1))
+        );
+
+        let text = ArenaBox::<&str>::try_from("copied").unwrap();
+        assert_eq!(*text.get(), "copied");
+    }
+}
@@ -0,0 +1,53 @@
+use bumpalo::Bump;
+
+/// An arena handle whose allocations are dropped instead of failing when the arena is full.
+///
+/// This is passed to the closure of [`ArenaBox::new_saturating`](crate::ArenaBox::new_saturating).
+/// Once the arena has reached its allocation limit, allocating a string returns `""` and
+/// allocating a slice returns an empty slice, so building the value carries on with lossy data
+/// instead of panicking.
+#[derive(Clone, Copy)]
+pub struct SaturatingArena<'a> {
+    arena: &'a Bump,
+}
+
+impl<'a> SaturatingArena<'a> {
+    pub(crate) fn new(arena: &'a Bump) -> Self {
+        SaturatingArena { arena }
+    }
+
+    /// Returns a reference to the underlying arena.
+    ///
+    /// Allocations made directly through it are not saturating: they panic when the arena is
+    /// full.
+    pub fn arena(&self) -> &'a Bump {
+        self.arena
+    }
+
+    /// Copies a string into the arena, or returns `""` if the arena is full.
+    pub fn alloc_str(&self, src: &str) -> &'a str {
+        self.arena.try_alloc_str(src).map_or("", |s| s)
+    }
+
+    /// Copies a slice into the arena, or returns an empty slice if the arena is full.
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &'a [T] {
+        self.arena.try_alloc_slice_copy(src).map_or(&[], |s| s)
+    }
+
+    /// Clones a slice into the arena, or returns an empty slice if the arena is full.
+    pub fn alloc_slice_clone<T: Clone>(&self, src: &[T]) -> &'a [T] {
+        self.arena.try_alloc_slice_clone(src).map_or(&[], |s| s)
+    }
+
+    /// Allocates a slice filled with the items of `iter`, or returns an empty slice if the arena
+    /// is full.
+    pub fn alloc_slice_fill_iter<T, I>(&self, iter: I) -> &'a [T]
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.arena
+            .try_alloc_slice_fill_iter(iter)
+            .map_or(&[], |s| s)
+    }
+}
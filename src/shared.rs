@@ -0,0 +1,143 @@
+use alloc::rc::Rc;
+use bumpalo::Bump;
+
+use crate::{ArenaBox, WithLifetime};
+
+/// A reference counted, read-only `ArenaBox`.
+///
+/// Cloning a `SharedArenaBox` only increments a reference count: all clones share the same
+/// arena and data, which are freed when the last clone is dropped. Since the data is shared,
+/// it cannot be mutated.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// pub struct Data<'a> {
+///    msg: &'a str,
+/// }
+///
+/// make_arena_version!(Data, pub ArenaData);
+///
+/// let shared = SharedArenaBox::from(ArenaData::new(|arena| Data {
+///     msg: arena.alloc_str("Something"),
+/// }));
+/// let other = shared.clone();
+///
+/// assert_eq!(other.get().msg, "Something");
+/// assert!(SharedArenaBox::ptr_eq(&shared, &other));
+/// ```
+pub struct SharedArenaBox<T: WithLifetime> {
+    inner: Rc<ArenaBox<T>>,
+}
+
+impl<T: WithLifetime> SharedArenaBox<T> {
+    /// Creates a new `SharedArenaBox`.
+    ///
+    /// This is equivalent to creating an [`ArenaBox`] with [`ArenaBox::new`] and converting it.
+    pub fn new<F>(build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> <T as WithLifetime>::With<'a>,
+    {
+        ArenaBox::new(build).into()
+    }
+
+    /// Get a reference to the data within the arena.
+    pub fn get<'b>(&'b self) -> &'b <T as WithLifetime>::With<'b> {
+        self.inner.get()
+    }
+
+    /// Returns `true` if both `SharedArenaBox`es share the same arena and data.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Rc::ptr_eq(&this.inner, &other.inner)
+    }
+
+    /// Returns the number of clones sharing this arena and data.
+    pub fn strong_count(this: &Self) -> usize {
+        Rc::strong_count(&this.inner)
+    }
+}
+
+impl<T: WithLifetime> Clone for SharedArenaBox<T> {
+    fn clone(&self) -> Self {
+        SharedArenaBox {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: WithLifetime> From<ArenaBox<T>> for SharedArenaBox<T> {
+    fn from(boxed: ArenaBox<T>) -> Self {
+        SharedArenaBox {
+            inner: Rc::new(boxed),
+        }
+    }
+}
+
+impl<T: core::fmt::Display + WithLifetime> core::fmt::Display for SharedArenaBox<T>
+where
+    for<'a> T::With<'a>: core::fmt::Display,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Display::fmt(&*self.inner, f)
+    }
+}
+
+impl<T: core::fmt::Debug + WithLifetime> core::fmt::Debug for SharedArenaBox<T>
+where
+    for<'a> T::With<'a>: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&*self.inner, f)
+    }
+}
+
+impl<T: WithLifetime> PartialEq for SharedArenaBox<T>
+where
+    for<'a> T::With<'a>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::make_arena_version;
+
+    #[derive(Debug, PartialEq)]
+    struct Data<'a> {
+        msg: &'a str,
+    }
+    make_arena_version!(Data, ArenaData);
+
+    #[test]
+    fn test_clone_shares_data() {
+        let a = SharedArenaBox::<Data<'static>>::new(|arena| Data {
+            msg: arena.alloc_str("shared"),
+        });
+        let b = a.clone();
+        assert_eq!(SharedArenaBox::strong_count(&a), 2);
+        assert!(SharedArenaBox::ptr_eq(&a, &b));
+        assert!(core::ptr::eq(a.get(), b.get()));
+
+        drop(a);
+        assert_eq!(SharedArenaBox::strong_count(&b), 1);
+        assert_eq!(b.get().msg, "shared");
+    }
+
+    #[test]
+    fn test_equality() {
+        let a = SharedArenaBox::from(ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("shared"),
+        }));
+        let b = SharedArenaBox::from(ArenaData::new(|arena| Data {
+            msg: arena.alloc_str("shared"),
+        }));
+        assert!(!SharedArenaBox::ptr_eq(&a, &b));
+        assert_eq!(a, b);
+        assert_eq!(r#"Data { msg: "shared" }"#, alloc::format!("{:?}", a));
+    }
+}
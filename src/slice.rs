@@ -0,0 +1,392 @@
+use alloc::boxed::Box;
+use bumpalo::Bump;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use crate::{ArenaBox, PinnedArena, WithLifetime};
+
+/// A smart pointer that holds an arena allocated slice and the arena in the same struct.
+///
+/// This is the slice counterpart of [`ArenaBox`]: it derefs to `[E]`, so all the slice methods
+/// like `len`, `iter` and `get`, as well as indexing, are available on it.
+///
+/// The element type is a plain type rather than a [`WithLifetime`] family, so the elements
+/// can't borrow from the arena: an `ArenaSlice<&str>` only holds `'static` strings. For
+/// elements referencing the arena, and for transforms like [`ArenaBox::map_elements`], use an
+/// `ArenaBox<&'static [E]>` instead, which converts into an `ArenaSlice` when its elements don't
+/// borrow from the arena.
+///
+/// Like the data of an `ArenaBox`, the elements are dropped with the `ArenaSlice`, before the
+/// arena is freed.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let src = [1, 2, 3];
+/// let slice = ArenaSlice::new(|arena| arena.alloc_slice_copy(&src));
+///
+/// assert_eq!(slice.len(), 3);
+/// assert_eq!(slice[1], 2);
+/// assert_eq!(slice.iter().sum::<i32>(), 6);
+/// ```
+pub struct ArenaSlice<E> {
+    arena: PinnedArena,
+    data: NonNull<[E]>,
+}
+
+impl<E> ArenaSlice<E> {
+    /// Creates a new `ArenaSlice` from a slice allocated in the arena.
+    ///
+    /// The slice is borrowed mutably, like the slices returned by the allocation methods of
+    /// [`Bump`], so the `ArenaSlice` owns its elements: it drops them when it is dropped, and they
+    /// can be moved out by iterating over it.
+    pub fn new<F>(build: F) -> Self
+    where
+        F: for<'a> FnOnce(&'a Bump) -> &'a mut [E],
+    {
+        let arena = Box::pin(Bump::new());
+        let arena_ref: &Bump = arena.as_ref().get_ref();
+        // The arena is pinned, so the slice stays valid for the lifetime of the `ArenaSlice`.
+        let data = NonNull::from(build(arena_ref));
+        ArenaSlice { arena, data }
+    }
+
+    /// Returns the total capacity of the chunks allocated by the arena, in bytes.
+    pub fn allocated_bytes(&self) -> usize {
+        self.arena.allocated_bytes()
+    }
+}
+
+/// Converts an `ArenaBox` holding a slice into an `ArenaSlice`, reusing its arena.
+///
+/// The elements are cloned into the arena, because an `ArenaSlice` owns its elements while the
+/// slice of an `ArenaBox` may be shared, like a slice borrowed from a `'static` source with
+/// [`ArenaBox::new_borrowing`]. The elements don't borrow from the arena, so the arenas retained
+/// by the `ArenaBox` from its sources are freed.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let numbers: ArenaBox<&[u32]> = (1..=3).collect();
+/// let squares = numbers.map_elements::<u32, _>(|_, n| n * n);
+///
+/// let slice = ArenaSlice::from(squares);
+/// assert_eq!(slice[..], [1, 4, 9]);
+/// ```
+impl<E> From<ArenaBox<&'static [E]>> for ArenaSlice<E>
+where
+    E: for<'a> WithLifetime<With<'a> = E> + Clone,
+{
+    fn from(boxed: ArenaBox<&'static [E]>) -> Self {
+        let (arena, retained_arenas, data) = boxed.into_parts();
+        // SAFETY: The data was built in the arenas, which are still alive.
+        let elements: &[E] = unsafe { *data.as_ptr() };
+        let data = NonNull::from(arena.as_ref().get_ref().alloc_slice_clone(elements));
+        retained_arenas.release();
+        ArenaSlice { arena, data }
+    }
+}
+
+impl<E> Drop for ArenaSlice<E> {
+    fn drop(&mut self) {
+        // SAFETY: The `ArenaSlice` owns its elements, which are never used again. The arena is
+        // only freed afterwards, so the destructors can still access it.
+        unsafe { core::ptr::drop_in_place(self.data.as_ptr()) };
+    }
+}
+
+impl<E> Deref for ArenaSlice<E> {
+    type Target = [E];
+
+    fn deref(&self) -> &[E] {
+        // SAFETY: The slice is guaranteed to be valid for the lifetime of the `ArenaSlice`.
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<E, I: core::slice::SliceIndex<[E]>> core::ops::Index<I> for ArenaSlice<E> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &I::Output {
+        &(**self)[index]
+    }
+}
+
+/// Iterates over the elements, moving them out of the arena, which is freed once the iterator
+/// is dropped.
+///
+/// The elements that are not yielded are dropped with the iterator, like with the `ArenaSlice`.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let slice = ArenaSlice::new(|arena| arena.alloc_slice_fill_iter(["a", "b"].map(String::from)));
+///
+/// let owned: Vec<String> = slice.into_iter().collect();
+/// assert_eq!(owned, ["a", "b"]);
+/// ```
+impl<E> IntoIterator for ArenaSlice<E> {
+    type Item = E;
+    type IntoIter = ArenaSliceIntoIter<E>;
+
+    fn into_iter(self) -> ArenaSliceIntoIter<E> {
+        let range = 0..self.len();
+        // The elements are moved out or dropped by the iterator instead of the `ArenaSlice`.
+        let this = core::mem::ManuallyDrop::new(self);
+        // SAFETY: `this` is never used or dropped again, so the arena is moved out exactly once.
+        let arena = unsafe { core::ptr::read(&this.arena) };
+        ArenaSliceIntoIter {
+            _arena: arena,
+            data: this.data,
+            range,
+        }
+    }
+}
+
+/// An iterator moving the elements out of an [`ArenaSlice`], which owns its arena.
+///
+/// This struct is created by the `into_iter` method of [`ArenaSlice`].
+pub struct ArenaSliceIntoIter<E> {
+    // Kept alive until the iterator is dropped, since the remaining elements are in it.
+    _arena: PinnedArena,
+    data: NonNull<[E]>,
+    // The positions of the elements not moved out yet.
+    range: core::ops::Range<usize>,
+}
+
+impl<E> ArenaSliceIntoIter<E> {
+    /// Moves the element at position `i` out of the slice.
+    ///
+    /// # Safety
+    ///
+    /// `i` must have just been taken out of `self.range`, so that each element is moved at most
+    /// once and never accessed afterwards.
+    unsafe fn move_out(&self, i: usize) -> E {
+        // SAFETY: The `ArenaSlice` owns its elements and `i` is in bounds. The caller guarantees
+        // the element is not used again.
+        unsafe { core::ptr::read(self.data.as_ptr().cast::<E>().add(i)) }
+    }
+}
+
+impl<E> Iterator for ArenaSliceIntoIter<E> {
+    type Item = E;
+
+    fn next(&mut self) -> Option<E> {
+        // SAFETY: `i` was just removed from the range of remaining elements.
+        self.range.next().map(|i| unsafe { self.move_out(i) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<E> DoubleEndedIterator for ArenaSliceIntoIter<E> {
+    fn next_back(&mut self) -> Option<E> {
+        // SAFETY: `i` was just removed from the range of remaining elements.
+        self.range.next_back().map(|i| unsafe { self.move_out(i) })
+    }
+}
+
+impl<E> ExactSizeIterator for ArenaSliceIntoIter<E> {}
+
+impl<E> Drop for ArenaSliceIntoIter<E> {
+    fn drop(&mut self) {
+        let remaining = core::ptr::slice_from_raw_parts_mut(
+            self.data
+                .as_ptr()
+                .cast::<E>()
+                .wrapping_add(self.range.start),
+            self.range.len(),
+        );
+        // SAFETY: The elements of the range were not moved out and are never used again. The
+        // arena is only freed afterwards, so the destructors can still access it.
+        unsafe { core::ptr::drop_in_place(remaining) };
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Debug for ArenaSlice<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<E: PartialEq> PartialEq for ArenaSlice<E> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<E: Eq> Eq for ArenaSlice<E> {}
+
+impl<E: PartialOrd> PartialOrd for ArenaSlice<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        (**self).partial_cmp(&**other)
+    }
+}
+
+impl<E: Ord> Ord for ArenaSlice<E> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl<E: core::hash::Hash> core::hash::Hash for ArenaSlice<E> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_access() {
+        let src = [10u32, 20, 30];
+        let slice = ArenaSlice::new(|arena| arena.alloc_slice_copy(&src));
+
+        assert_eq!(slice.len(), 3);
+        assert_eq!(slice.get(2), Some(&30));
+        assert_eq!(slice.get(3), None);
+        assert_eq!(slice[0], 10);
+        assert_eq!(slice.iter().copied().collect::<alloc::vec::Vec<_>>(), src);
+        assert_eq!(&slice[1..], [20, 30]);
+    }
+
+    #[test]
+    fn test_index_range() {
+        fn middle<S: core::ops::Index<core::ops::Range<usize>, Output = [u8]>>(s: &S) -> &[u8] {
+            &s[1..3]
+        }
+
+        let slice = ArenaSlice::new(|arena| arena.alloc_slice_copy(b"abcd"));
+        assert_eq!(middle(&slice), b"bc");
+        assert_eq!(&slice[..2], b"ab");
+        assert_eq!(&slice[2..], b"cd");
+        assert_eq!(&slice[1..=2], b"bc");
+        assert_eq!(&slice[..], b"abcd");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_index_range_out_of_bounds() {
+        let slice = ArenaSlice::new(|arena| arena.alloc_slice_copy(b"abcd"));
+        let _ = &slice[2..5];
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let slice =
+            ArenaSlice::new(|arena| arena.alloc_slice_fill_iter((0..5).map(alloc::rc::Rc::new)));
+        let mut iter = slice.into_iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next_back().as_deref(), Some(&4));
+        let first = iter.next().unwrap();
+        // The element was moved out, not cloned.
+        assert_eq!(alloc::rc::Rc::strong_count(&first), 1);
+        assert_eq!(*first, 0);
+        assert_eq!(iter.map(|n| *n).collect::<alloc::vec::Vec<_>>(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_drop_elements() {
+        use alloc::rc::Rc;
+
+        let counter = Rc::new(());
+        let slice = ArenaSlice::new(|arena| arena.alloc_slice_fill_with(3, |_| counter.clone()));
+        assert_eq!(Rc::strong_count(&counter), 4);
+        drop(slice);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_into_iter_moves_non_clone() {
+        use alloc::rc::Rc;
+        use core::cell::Cell;
+
+        struct Token(Rc<Cell<usize>>);
+        impl Drop for Token {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let slice =
+            ArenaSlice::new(|arena| arena.alloc_slice_fill_with(3, |_| Token(drops.clone())));
+        let mut iter = slice.into_iter();
+        drop(iter.next());
+        drop(iter.next_back());
+        assert_eq!(drops.get(), 2);
+
+        // The element that was not yielded is dropped with the iterator.
+        drop(iter);
+        assert_eq!(drops.get(), 3);
+        assert_eq!(Rc::strong_count(&drops), 1);
+    }
+
+    #[test]
+    fn test_from_arena_box() {
+        let words = ArenaBox::<&[&str]>::new(|arena| {
+            arena.alloc_slice_fill_iter(["a", "bc", "def"].map(|s| &*arena.alloc_str(s)))
+        });
+        let lengths = words.map_elements::<usize, _>(|_, word| word.len());
+        let allocated = lengths.allocated_bytes();
+        let slice = ArenaSlice::from(lengths);
+        assert_eq!(slice[..], [1, 2, 3]);
+        // The clones are allocated in the arena of the `ArenaBox`.
+        assert_eq!(slice.allocated_bytes(), allocated);
+
+        static NAMES: [u8; 3] = *b"xyz";
+        let borrowed = ArenaBox::<&[u8]>::new_borrowing(&NAMES, |_, names| names);
+        let slice = ArenaSlice::from(borrowed);
+        assert_eq!(slice[..], NAMES);
+        assert!(!core::ptr::eq(&slice[0], &NAMES[0]));
+    }
+
+    #[test]
+    fn test_equality() {
+        let a = ArenaSlice::new(|arena| arena.alloc_slice_copy(&["a", "b"]));
+        let b = ArenaSlice::new(|arena| arena.alloc_slice_fill_iter(["a", "b"]));
+        let c = ArenaSlice::new(|arena| arena.alloc_slice_copy(&["a"]));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(r#"["a", "b"]"#, alloc::format!("{:?}", a));
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut slices = alloc::vec![
+            ArenaSlice::new(|arena| arena.alloc_slice_copy(&[2, 1])),
+            ArenaSlice::new(|arena| arena.alloc_slice_copy(&[1, 2, 3])),
+            ArenaSlice::new(|arena| arena.alloc_slice_copy(&[])),
+            ArenaSlice::new(|arena| arena.alloc_slice_copy(&[1, 2])),
+        ];
+        slices.sort();
+        let sorted: alloc::vec::Vec<&[i32]> = slices.iter().map(|s| &**s).collect();
+        assert_eq!(sorted, [&[][..], &[1, 2], &[1, 2, 3], &[2, 1]]);
+
+        let nan = ArenaSlice::new(|arena| arena.alloc_slice_copy(&[f64::NAN]));
+        assert_eq!(nan.partial_cmp(&nan), None);
+    }
+
+    // The hash only depends on the elements, not on the interior mutability of the arena.
+    #[allow(clippy::mutable_key_type)]
+    #[test]
+    fn test_hash() {
+        extern crate std;
+
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(ArenaSlice::new(|arena| arena.alloc_slice_copy(&[1, 2]))));
+        assert!(!set.insert(ArenaSlice::new(|arena| arena.alloc_slice_fill_iter(1..3))));
+        assert!(set.insert(ArenaSlice::new(|arena| arena.alloc_slice_copy(&[2, 1]))));
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,37 @@
+/// A snapshot of the memory usage of an [`ArenaBox`](crate::ArenaBox), returned by
+/// [`ArenaBox::arena_stats`](crate::ArenaBox::arena_stats).
+///
+/// It gathers the introspection methods of `ArenaBox` into one report. With the `serde`
+/// feature, it implements `Serialize`, so it can be fed to metrics pipelines directly.
+///
+/// # Example
+///
+/// ```
+/// # use arena_box::*;
+///
+/// let boxed = ArenaBox::<&str>::new(|arena| arena.alloc_str("Something"));
+/// let stats = boxed.arena_stats();
+///
+/// assert_eq!(stats.allocated_bytes, boxed.allocated_bytes());
+/// assert_eq!(stats.chunk_count, 1);
+/// assert!(stats.used_bytes >= "Something".len());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ArenaStats {
+    /// The total capacity of the chunks allocated by the arena, in bytes, as returned by
+    /// [`ArenaBox::allocated_bytes`](crate::ArenaBox::allocated_bytes).
+    pub allocated_bytes: usize,
+    /// The bytes in use across all chunks of the arena.
+    pub used_bytes: usize,
+    /// The bytes that were in use when the data was last built, as returned by
+    /// [`ArenaBox::retained_estimate`](crate::ArenaBox::retained_estimate).
+    pub built_bytes: usize,
+    /// The number of chunks allocated by the arena.
+    pub chunk_count: usize,
+    /// The number of arenas of other `ArenaBox`es kept alive by this one, like the sources of
+    /// [`ArenaBox::new_from2`](crate::ArenaBox::new_from2). They are not counted in the other
+    /// fields.
+    pub retained_arenas: usize,
+}
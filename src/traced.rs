@@ -0,0 +1,81 @@
+use bumpalo::Bump;
+use core::cell::RefCell;
+
+type Callback<'c> = RefCell<dyn FnMut(usize, Option<&str>) + 'c>;
+
+/// An arena handle that reports each allocation to a callback.
+///
+/// This is passed to the closure of [`ArenaBox::new_traced`](crate::ArenaBox::new_traced). Each
+/// allocation made through it calls the callback with its size in bytes and the label set with
+/// [`TracedArena::labeled`], if any, which helps understanding how a complex build closure uses
+/// the arena.
+#[derive(Clone, Copy)]
+pub struct TracedArena<'a, 'c> {
+    arena: &'a Bump,
+    label: Option<&'c str>,
+    callback: &'c Callback<'c>,
+}
+
+impl<'a, 'c> TracedArena<'a, 'c> {
+    pub(crate) fn new(arena: &'a Bump, callback: &'c Callback<'c>) -> Self {
+        TracedArena {
+            arena,
+            label: None,
+            callback,
+        }
+    }
+
+    /// Returns a reference to the underlying arena.
+    ///
+    /// Allocations made directly through it are not reported.
+    pub fn arena(&self) -> &'a Bump {
+        self.arena
+    }
+
+    /// Returns a copy of this handle whose allocations are reported with `label`.
+    pub fn labeled(self, label: &'c str) -> Self {
+        TracedArena {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    fn trace(&self, size: usize) {
+        (self.callback.borrow_mut())(size, self.label);
+    }
+
+    /// Allocates a value in the arena.
+    pub fn alloc<T>(&self, value: T) -> &'a mut T {
+        self.trace(size_of::<T>());
+        self.arena.alloc(value)
+    }
+
+    /// Copies a string into the arena.
+    pub fn alloc_str(&self, src: &str) -> &'a str {
+        self.trace(src.len());
+        self.arena.alloc_str(src)
+    }
+
+    /// Copies a slice into the arena.
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &'a [T] {
+        self.trace(size_of_val(src));
+        self.arena.alloc_slice_copy(src)
+    }
+
+    /// Clones a slice into the arena.
+    pub fn alloc_slice_clone<T: Clone>(&self, src: &[T]) -> &'a [T] {
+        self.trace(size_of_val(src));
+        self.arena.alloc_slice_clone(src)
+    }
+
+    /// Allocates a slice filled with the items of `iter`.
+    pub fn alloc_slice_fill_iter<T, I>(&self, iter: I) -> &'a [T]
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let iter = iter.into_iter();
+        self.trace(size_of::<T>() * iter.len());
+        self.arena.alloc_slice_fill_iter(iter)
+    }
+}